@@ -1,10 +1,18 @@
-use std::{fmt::Display, str::FromStr};
+use std::{
+    fmt::Display,
+    io::{BufRead, BufReader, Write},
+    str::FromStr,
+};
 
 use clap::{Parser, Subcommand};
 use enum_dispatch::enum_dispatch;
 
 use crate::{
-    process::base64::{process_decode, process_encode},
+    process::{
+        base64::{decode_payload, open_decode_input, process_encode_data_uri},
+        encoding::{decode_reader, process_encoding_encode},
+    },
+    utils::{get_reader, AtomicWriter},
     CmdExector,
 };
 
@@ -13,9 +21,9 @@ use super::verify_file;
 #[derive(Debug, Subcommand)]
 #[enum_dispatch(CmdExector)]
 pub enum Base64SubCommand {
-    #[command(about = "Base64编码")]
+    #[command(about = "Base64/hex/base32/base58/base85/百分号编码")]
     Encode(Base64EncodeOpts),
-    #[command(about = "Base64解码")]
+    #[command(about = "Base64/hex/base32/base58/base85/百分号解码")]
     Decode(Base64DecodeOpts),
 }
 
@@ -28,18 +36,42 @@ pub enum Base64SubCommand {
 //     }
 // }
 
+// cargo run -- base64 encode -i fixtrues/ed25519.pk --format hex --separator :
 #[derive(Debug, Parser)]
 pub struct Base64EncodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
-    #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
-    pub format: Base64Format,
+    /// 编码格式：base64 的各种格式（standard、urlsafe 等），或 hex、hex-upper、base32、
+    /// base32-crockford、base58、ascii85、z85、percent
+    #[arg(long, value_parser = parse_encoding, default_value = "standard")]
+    pub format: Encoding,
+    /// hex 字节之间的分隔符，如 `:` 或空格，仅用于 hex 和 hex-upper
+    #[arg(long, conflicts_with = "data_uri")]
+    pub separator: Option<String>,
+    /// 输出 `data:<mime>;base64,...`，MIME 类型根据文件内容或扩展名识别
+    #[arg(long, conflicts_with = "format")]
+    pub data_uri: bool,
 }
 
 impl CmdExector for Base64EncodeOpts {
     async fn execute(&self) -> anyhow::Result<()> {
-        let encodeed = process_encode(&self.input, self.format)?;
-        println!("{}", encodeed);
+        self.format.check_separator(self.separator.as_deref())?;
+        let mut stdout = std::io::stdout().lock();
+        if self.data_uri {
+            process_encode_data_uri(&self.input, &mut stdout)?;
+        } else {
+            process_encoding_encode(
+                &self.input,
+                &mut stdout,
+                self.format,
+                self.separator.as_deref(),
+            )?;
+        }
+        // 折行格式自带结尾换行
+        if self.data_uri || !matches!(self.format, Encoding::Base64(format) if format.is_wrapped())
+        {
+            writeln!(stdout)?;
+        }
         Ok(())
     }
 }
@@ -48,33 +80,100 @@ impl CmdExector for Base64EncodeOpts {
 pub struct Base64DecodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
-    #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
-    pub format: Base64Format,
+    /// 编码格式，取值同 `encode --format`
+    #[arg(long, value_parser = parse_encoding, default_value = "standard")]
+    pub format: Encoding,
+    /// hex 字节之间的分隔符，解码时会被忽略，仅用于 hex 和 hex-upper
+    #[arg(long)]
+    pub separator: Option<String>,
+    /// 解码结果写入的文件，默认原样写到标准输出；
+    /// 输入为 data URI 时默认写入 `output.<按 MIME 推断的扩展名>`，该文件已存在时报错
+    #[arg(short, long)]
+    pub output: Option<String>,
+    /// 按 UTF-8 文本打印（末尾追加换行），非法 UTF-8 会报错
+    #[arg(long, conflicts_with = "output")]
+    pub utf8: bool,
 }
 
 impl CmdExector for Base64DecodeOpts {
     async fn execute(&self) -> anyhow::Result<()> {
-        let decodeed = process_decode(&self.input, self.format)?;
-        println!("{}", String::from_utf8(decodeed)?);
+        self.format.check_separator(self.separator.as_deref())?;
+        // 只有 base64 输入才识别 data URI，其他编码（如 ascii85）中 `data:` 也可能是合法内容
+        let (mut reader, data_uri) = match self.format {
+            Encoding::Base64(_) => open_decode_input(&self.input)?,
+            _ => {
+                let reader: Box<dyn BufRead> = Box::new(BufReader::new(get_reader(&self.input)?));
+                (reader, None)
+            }
+        };
+        let mut decode = |writer: &mut dyn Write| match self.format {
+            Encoding::Base64(format) => {
+                decode_payload(&mut reader, writer, format, data_uri.as_ref())
+            }
+            encoding => decode_reader(&mut reader, writer, encoding, self.separator.as_deref()),
+        };
+        if self.utf8 {
+            let mut decodeed = Vec::new();
+            decode(&mut decodeed)?;
+            println!("{}", String::from_utf8(decodeed)?);
+            return Ok(());
+        }
+
+        // 解码成功后才替换目标文件，非法输入不会留下空文件；
+        // 自动命名的文件不会覆盖当前目录下已有的同名文件
+        let (output, mut writer) = match (&self.output, &data_uri) {
+            (Some(output), _) => (output.clone(), AtomicWriter::new(output)?),
+            (None, Some(uri)) => {
+                let output = format!("output.{}", uri.extension());
+                let writer = AtomicWriter::create_new(&output)
+                    .map_err(|e| e.context("请使用 -o 指定输出文件"))?;
+                (output, writer)
+            }
+            (None, None) => ("-".to_string(), AtomicWriter::new("-")?),
+        };
+        decode(&mut writer)?;
+        writer.commit()?;
+        if let Some(uri) = &data_uri {
+            eprintln!("已将 {} 数据写入 {}", uri.mime, output);
+        }
         Ok(())
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Base64Format {
+    /// 标准字母表，带填充
     Standard,
+    /// 标准字母表，无填充
+    StandardNoPad,
+    /// URL 安全字母表，无填充
     UrlSafe,
+    /// URL 安全字母表，带填充
+    UrlSafePad,
+    /// MIME：标准字母表，每 76 列以 CRLF 折行
+    Mime,
+    /// PEM：标准字母表，每 64 列以 LF 折行
+    Pem,
+    /// 仅用于解码：忽略空白，自动识别字母表和填充
+    Lenient,
 }
 
-fn parse_base64_format(value: &str) -> anyhow::Result<Base64Format> {
-    value.parse()
+impl Base64Format {
+    pub fn is_wrapped(&self) -> bool {
+        matches!(self, Base64Format::Mime | Base64Format::Pem)
+    }
 }
 
 impl From<Base64Format> for &'static str {
     fn from(value: Base64Format) -> Self {
         match value {
             Base64Format::Standard => "standard",
+            Base64Format::StandardNoPad => "standard-nopad",
             Base64Format::UrlSafe => "urlsafe",
+            Base64Format::UrlSafePad => "urlsafe-pad",
+            Base64Format::Mime => "mime",
+            Base64Format::Pem => "pem",
+            Base64Format::Lenient => "lenient",
         }
     }
 }
@@ -85,7 +184,12 @@ impl FromStr for Base64Format {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "standard" => Ok(Base64Format::Standard),
+            "standard-nopad" => Ok(Base64Format::StandardNoPad),
             "urlsafe" => Ok(Base64Format::UrlSafe),
+            "urlsafe-pad" => Ok(Base64Format::UrlSafePad),
+            "mime" => Ok(Base64Format::Mime),
+            "pem" => Ok(Base64Format::Pem),
+            "lenient" => Ok(Base64Format::Lenient),
             _ => anyhow::bail!("不支持的格式"),
         }
     }
@@ -96,3 +200,76 @@ impl Display for Base64Format {
         write!(f, "{}", Into::<&str>::into(*self))
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+pub enum Encoding {
+    Hex,
+    HexUpper,
+    /// RFC 4648 base32，带填充
+    Base32,
+    Base32Crockford,
+    /// 比特币字母表
+    Base58,
+    Ascii85,
+    Z85,
+    /// URL 百分号编码
+    Percent,
+    /// `base64`、`base64-<格式>` 或直接写 base64 格式名（如 `urlsafe`）
+    Base64(Base64Format),
+}
+
+impl Encoding {
+    /// `--separator` 只对 hex 有意义，其他格式下报错而不是静默忽略
+    pub fn check_separator(&self, separator: Option<&str>) -> anyhow::Result<()> {
+        match (self, separator) {
+            (Encoding::Hex | Encoding::HexUpper, _) | (_, None) => Ok(()),
+            _ => anyhow::bail!(
+                "--separator 只能用于 hex 或 hex-upper 格式，当前格式为 {}",
+                self
+            ),
+        }
+    }
+}
+
+fn parse_encoding(value: &str) -> anyhow::Result<Encoding> {
+    value.parse()
+}
+
+impl FromStr for Encoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        match s.as_str() {
+            "hex" => Ok(Encoding::Hex),
+            "hex-upper" => Ok(Encoding::HexUpper),
+            "base32" => Ok(Encoding::Base32),
+            "base32-crockford" => Ok(Encoding::Base32Crockford),
+            "base58" => Ok(Encoding::Base58),
+            "ascii85" => Ok(Encoding::Ascii85),
+            "z85" => Ok(Encoding::Z85),
+            "percent" => Ok(Encoding::Percent),
+            "base64" => Ok(Encoding::Base64(Base64Format::Standard)),
+            // 兼容原来的 `--format standard` 等写法
+            _ => Ok(Encoding::Base64(
+                s.strip_prefix("base64-").unwrap_or(&s).parse()?,
+            )),
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Hex => write!(f, "hex"),
+            Encoding::HexUpper => write!(f, "hex-upper"),
+            Encoding::Base32 => write!(f, "base32"),
+            Encoding::Base32Crockford => write!(f, "base32-crockford"),
+            Encoding::Base58 => write!(f, "base58"),
+            Encoding::Ascii85 => write!(f, "ascii85"),
+            Encoding::Z85 => write!(f, "z85"),
+            Encoding::Percent => write!(f, "percent"),
+            Encoding::Base64(format) => write!(f, "base64-{}", format),
+        }
+    }
+}
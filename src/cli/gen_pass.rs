@@ -1,31 +1,60 @@
 use clap::Parser;
 
-use crate::{process::gen_pass::process_gen_pass, CmdExector};
+use crate::{
+    process::gen_pass::{
+        process_gen_pass, process_gen_pass_with_policy, process_gen_pattern,
+        process_gen_pronounceable, PasswordPolicy,
+    },
+    utils::CliRng,
+    CmdExector,
+};
+
+use super::verify_file;
 use zxcvbn::zxcvbn;
 
 #[derive(Debug, Parser)]
 pub struct GenPassOpts {
     #[arg(long, default_value_t = 16)]
-    pub length: u8,
-    #[arg(long, default_value_t = true)]
+    pub length: usize,
+    #[arg(long, default_value_t = true, conflicts_with = "pronounceable")]
     pub uppercase: bool,
     #[arg(long, default_value_t = true)]
     pub lowercase: bool,
-    #[arg(long, default_value_t = true)]
+    #[arg(long, default_value_t = true, conflicts_with = "pronounceable")]
     pub number: bool,
-    #[arg(long, default_value_t = true)]
+    #[arg(long, default_value_t = true, conflicts_with = "pronounceable")]
     pub symbol: bool,
+    /// 按模板生成，如 `Cvccvc-9999-!!`（c 辅音、v 元音、9 数字、! 符号，大写字母表示大写）
+    #[arg(long, conflicts_with = "pronounceable")]
+    pub pattern: Option<String>,
+    /// 生成辅音/元音交替、便于口述的密码，只含小写字母
+    #[arg(long, conflicts_with = "policy")]
+    pub pronounceable: bool,
+    /// 密码策略文件（.yaml/.toml），生成后按策略重新校验
+    #[arg(long, value_parser = verify_file, conflicts_with = "pattern")]
+    pub policy: Option<String>,
 }
 
 impl CmdExector for GenPassOpts {
     async fn execute(&self) -> anyhow::Result<()> {
-        let password = process_gen_pass(
-            self.length,
-            self.lowercase,
-            self.uppercase,
-            self.number,
-            self.symbol,
-        )?;
+        let mut rng = CliRng::os();
+        let password = if let Some(pattern) = &self.pattern {
+            process_gen_pattern(pattern, &mut rng)?
+        } else if let Some(policy) = &self.policy {
+            let policy = PasswordPolicy::load(policy)?;
+            process_gen_pass_with_policy(self.length, &policy, &mut rng)?
+        } else if self.pronounceable {
+            process_gen_pronounceable(self.length, &mut rng)?
+        } else {
+            process_gen_pass(
+                self.length,
+                self.lowercase,
+                self.uppercase,
+                self.number,
+                self.symbol,
+                &mut rng,
+            )?
+        };
         println!("{}", password);
         let password_strength = zxcvbn(&password, &[]);
         eprintln!(
@@ -0,0 +1,151 @@
+use std::{fmt::Display, str::FromStr};
+
+use clap::Parser;
+
+use crate::{
+    process::hash::{process_hash, process_hash_check, CheckStatus},
+    CmdExector,
+};
+
+use super::verify_file;
+
+// cargo run -- hash -i Cargo.toml README.md --algo sha256
+// cargo run -- hash --check SHA256SUMS
+#[derive(Debug, Parser)]
+pub struct HashOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-", num_args = 1..)]
+    pub input: Vec<String>,
+    #[arg(long, value_parser = parse_hash_algorithm, default_value = "sha256")]
+    pub algo: HashAlgorithm,
+    #[arg(long, value_parser = parse_digest_format, default_value = "hex")]
+    pub format: DigestFormat,
+    /// 校验 `sha256sum` 格式的校验和文件，`-` 表示从标准输入读取
+    #[arg(short, long, value_parser = verify_file, conflicts_with = "input")]
+    pub check: Option<String>,
+}
+
+impl CmdExector for HashOpts {
+    async fn execute(&self) -> anyhow::Result<()> {
+        if let Some(check) = &self.check {
+            let results = process_hash_check(check, self.algo, self.format)?;
+            let failed = results
+                .iter()
+                .filter(|r| r.status != CheckStatus::Ok)
+                .count();
+            // 与 sha256sum -c 一致：无法读取的文件单独标出，错误信息输出到标准错误
+            for result in &results {
+                match &result.status {
+                    CheckStatus::Ok => println!("{}: OK", result.path),
+                    CheckStatus::Mismatch => println!("{}: FAILED", result.path),
+                    CheckStatus::Unreadable(err) => {
+                        eprintln!("{}: {}", result.path, err);
+                        println!("{}: FAILED open or read", result.path);
+                    }
+                }
+            }
+            if failed > 0 {
+                anyhow::bail!("{} 个文件校验失败（共 {} 个）", failed, results.len());
+            }
+            return Ok(());
+        }
+
+        for input in &self.input {
+            let digest = process_hash(input, self.algo, self.format)?;
+            println!("{}  {}", digest, input);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Sha3_256,
+    Blake3,
+    Crc32,
+    /// XXH3 64 位
+    XxHash,
+}
+
+fn parse_hash_algorithm(value: &str) -> anyhow::Result<HashAlgorithm> {
+    value.parse()
+}
+
+impl From<HashAlgorithm> for &'static str {
+    fn from(value: HashAlgorithm) -> Self {
+        match value {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Sha3_256 => "sha3-256",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Crc32 => "crc32",
+            HashAlgorithm::XxHash => "xxhash",
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            "sha3-256" => Ok(HashAlgorithm::Sha3_256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "crc32" => Ok(HashAlgorithm::Crc32),
+            "xxhash" | "xxh3" => Ok(HashAlgorithm::XxHash),
+            _ => anyhow::bail!("不支持的算法"),
+        }
+    }
+}
+
+impl Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DigestFormat {
+    Hex,
+    Base64,
+}
+
+fn parse_digest_format(value: &str) -> anyhow::Result<DigestFormat> {
+    value.parse()
+}
+
+impl From<DigestFormat> for &'static str {
+    fn from(value: DigestFormat) -> Self {
+        match value {
+            DigestFormat::Hex => "hex",
+            DigestFormat::Base64 => "base64",
+        }
+    }
+}
+
+impl FromStr for DigestFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hex" => Ok(DigestFormat::Hex),
+            "base64" => Ok(DigestFormat::Base64),
+            _ => anyhow::bail!("不支持的格式"),
+        }
+    }
+}
+
+impl Display for DigestFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
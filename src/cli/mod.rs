@@ -1,8 +1,10 @@
 pub mod base64;
 pub mod csv;
 pub mod gen_pass;
+pub mod hash;
 pub mod http;
 pub mod jwt;
+pub mod otp;
 pub mod text;
 
 use std::path::{Path, PathBuf};
@@ -12,6 +14,7 @@ use clap::{Parser, Subcommand};
 use csv::CsvOpts;
 use enum_dispatch::enum_dispatch;
 use gen_pass::GenPassOpts;
+use hash::HashOpts;
 
 // use crate::CmdExector;
 
@@ -29,14 +32,22 @@ pub enum SubCommand {
     Csv(CsvOpts),
     #[command(about = "随机生成密码")]
     GenPass(GenPassOpts),
-    #[command(subcommand, about = "Base64编码或解码")]
+    #[command(
+        subcommand,
+        visible_alias = "encoding",
+        about = "Base64/hex/base32/base58/base85/百分号编码或解码"
+    )]
     Base64(Base64SubCommand),
+    #[command(about = "计算或校验文件摘要")]
+    Hash(HashOpts),
     #[command(subcommand, about = "文本签名或验证")]
     Text(text::TextSubCommand),
     #[command(subcommand, about = "通过HTTP服务文件")]
     Http(http::HttpSubCommand),
     #[command(subcommand, about = "JWT签名或验证")]
     Jwt(jwt::JwtSubCommand),
+    #[command(subcommand, about = "TOTP/HOTP一次性密码")]
+    Otp(otp::OtpSubCommand),
 }
 
 // impl CmdExector for SubCommand {
@@ -79,4 +90,82 @@ mod tests {
         assert!(verify_file("Cargo.toml").is_ok());
         assert!(verify_file("Cargo.toml1").is_err());
     }
+
+    #[test]
+    fn test_otp_rejects_invalid_params() {
+        for args in [
+            ["rcli", "otp", "generate", "--length", "0"],
+            ["rcli", "otp", "generate", "--period", "0"],
+            ["rcli", "otp", "generate", "--digits", "0"],
+        ] {
+            assert!(Opts::try_parse_from(args).is_err());
+        }
+        let verify = [
+            "rcli", "otp", "verify", "-s", "GEZDGNBV", "--code", "123456",
+        ];
+        assert!(Opts::try_parse_from([&verify[..], &["--skew", "11"]].concat()).is_err());
+        assert!(Opts::try_parse_from([&verify[..], &["--skew", "10"]].concat()).is_ok());
+        assert!(Opts::try_parse_from(["rcli", "otp", "generate", "--length", "16"]).is_ok());
+    }
+
+    #[test]
+    fn test_pronounceable_rejects_charset_flags() {
+        for flag in ["--uppercase", "--number", "--symbol"] {
+            assert!(Opts::try_parse_from(["rcli", "gen-pass", "--pronounceable", flag]).is_err());
+        }
+        assert!(Opts::try_parse_from(["rcli", "gen-pass", "--pronounceable"]).is_ok());
+        assert!(Opts::try_parse_from(["rcli", "gen-pass", "--uppercase", "--number"]).is_ok());
+    }
+
+    #[test]
+    fn test_separator_only_for_hex() -> anyhow::Result<()> {
+        for format in ["hex", "hex-upper"] {
+            format
+                .parse::<base64::Encoding>()?
+                .check_separator(Some(":"))?;
+        }
+        for format in ["base32", "base58", "standard", "percent"] {
+            let encoding = format.parse::<base64::Encoding>()?;
+            assert!(encoding.check_separator(Some(":")).is_err());
+            assert!(encoding.check_separator(None).is_ok());
+        }
+        assert!(Opts::try_parse_from(["rcli", "encoding", "encode", "--format", "hex"]).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_rejects_cipher_formats() {
+        for args in [
+            [
+                "rcli",
+                "text",
+                "sign",
+                "--key",
+                "Cargo.toml",
+                "--format",
+                "aes-256-gcm",
+            ],
+            [
+                "rcli",
+                "text",
+                "sign",
+                "--key",
+                "Cargo.toml",
+                "--format",
+                "x25519",
+            ],
+        ] {
+            assert!(Opts::try_parse_from(args).is_err());
+        }
+        assert!(Opts::try_parse_from([
+            "rcli",
+            "text",
+            "generate",
+            "-o",
+            ".",
+            "--format",
+            "aes-256-gcm"
+        ])
+        .is_ok());
+    }
 }
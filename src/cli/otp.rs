@@ -0,0 +1,204 @@
+use std::{fmt::Display, str::FromStr};
+
+use clap::{Args, Parser, Subcommand};
+use enum_dispatch::enum_dispatch;
+
+use crate::{
+    process::otp::{
+        process_otp_code, process_otp_qr, process_otp_secret, process_otp_uri, process_otp_verify,
+        OtpParams, MAX_SKEW,
+    },
+    utils::CliRng,
+    CmdExector,
+};
+
+#[derive(Debug, Subcommand)]
+#[enum_dispatch(CmdExector)]
+pub enum OtpSubCommand {
+    #[command(about = "生成base32密钥")]
+    Generate(OtpGenerateOpts),
+    #[command(about = "生成otpauth://配置URI")]
+    Uri(OtpUriOpts),
+    #[command(about = "计算当前验证码")]
+    Code(OtpCodeOpts),
+    #[command(about = "校验验证码")]
+    Verify(OtpVerifyOpts),
+}
+
+#[derive(Debug, Clone, Copy, Args)]
+pub struct OtpParamsOpts {
+    #[arg(long, value_parser = parse_otp_algorithm, default_value = "sha1")]
+    pub algorithm: OtpAlgorithm,
+    #[arg(long, value_parser = clap::value_parser!(u32).range(6..=8), default_value_t = 6)]
+    pub digits: u32,
+    /// TOTP 时间步长（秒）
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..), default_value_t = 30)]
+    pub period: u64,
+    /// 指定计数器时使用 HOTP，否则使用 TOTP
+    #[arg(long)]
+    pub counter: Option<u64>,
+}
+
+impl From<OtpParamsOpts> for OtpParams {
+    fn from(value: OtpParamsOpts) -> Self {
+        Self {
+            algorithm: value.algorithm,
+            digits: value.digits,
+            period: value.period,
+            counter: value.counter,
+        }
+    }
+}
+
+// cargo run -- otp generate --account alice@example.com --issuer acme --qr
+#[derive(Debug, Parser)]
+pub struct OtpGenerateOpts {
+    /// 密钥字节数，RFC 4226 要求至少 128 位
+    #[arg(
+        long,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(16..),
+        default_value_t = 20
+    )]
+    pub length: usize,
+    #[arg(long)]
+    pub account: Option<String>,
+    #[arg(long, requires = "account")]
+    pub issuer: Option<String>,
+    #[arg(long, requires = "account")]
+    pub qr: bool,
+    #[command(flatten)]
+    pub params: OtpParamsOpts,
+}
+
+impl CmdExector for OtpGenerateOpts {
+    async fn execute(&self) -> anyhow::Result<()> {
+        let secret = process_otp_secret(self.length, &mut CliRng::os());
+        println!("{}", secret);
+        if let Some(account) = &self.account {
+            let uri =
+                process_otp_uri(&secret, account, self.issuer.as_deref(), self.params.into())?;
+            println!("{}", uri);
+            if self.qr {
+                println!("{}", process_otp_qr(&uri)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpUriOpts {
+    #[arg(short, long)]
+    pub secret: String,
+    #[arg(long)]
+    pub account: String,
+    #[arg(long)]
+    pub issuer: Option<String>,
+    #[arg(long)]
+    pub qr: bool,
+    #[command(flatten)]
+    pub params: OtpParamsOpts,
+}
+
+impl CmdExector for OtpUriOpts {
+    async fn execute(&self) -> anyhow::Result<()> {
+        let uri = process_otp_uri(
+            &self.secret,
+            &self.account,
+            self.issuer.as_deref(),
+            self.params.into(),
+        )?;
+        println!("{}", uri);
+        if self.qr {
+            println!("{}", process_otp_qr(&uri)?);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpCodeOpts {
+    #[arg(short, long)]
+    pub secret: String,
+    #[command(flatten)]
+    pub params: OtpParamsOpts,
+}
+
+impl CmdExector for OtpCodeOpts {
+    async fn execute(&self) -> anyhow::Result<()> {
+        let code = process_otp_code(&self.secret, self.params.into())?;
+        println!("{}", code);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    #[arg(short, long)]
+    pub secret: String,
+    #[arg(long)]
+    pub code: String,
+    /// 允许偏差的时间步（TOTP）或向后查找的计数器数量（HOTP），最多 10
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(0..=MAX_SKEW),
+        default_value_t = 1
+    )]
+    pub skew: u64,
+    #[command(flatten)]
+    pub params: OtpParamsOpts,
+}
+
+impl CmdExector for OtpVerifyOpts {
+    async fn execute(&self) -> anyhow::Result<()> {
+        let matched = process_otp_verify(&self.secret, &self.code, self.skew, self.params.into())?;
+        match matched {
+            Some(counter) => {
+                println!("true");
+                eprintln!("匹配的计数器：{}", counter);
+            }
+            None => println!("false"),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+fn parse_otp_algorithm(value: &str) -> anyhow::Result<OtpAlgorithm> {
+    value.parse()
+}
+
+impl From<OtpAlgorithm> for &'static str {
+    fn from(value: OtpAlgorithm) -> Self {
+        match value {
+            OtpAlgorithm::Sha1 => "sha1",
+            OtpAlgorithm::Sha256 => "sha256",
+            OtpAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            _ => anyhow::bail!("不支持的算法"),
+        }
+    }
+}
+
+impl Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -1,13 +1,25 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use clap::{Parser, Subcommand};
 use enum_dispatch::enum_dispatch;
-use tokio::fs;
+use tokio::{fs, io::AsyncWriteExt};
 
 use crate::{
-    process::text::{
-        process_decrypt, process_encrypt, process_gen_key, process_sign, process_verify,
+    process::{
+        manifest::{process_manifest_sign, process_manifest_verify},
+        text::{
+            process_decrypt, process_decrypt_with_identities, process_decrypt_with_passphrase,
+            process_encrypt, process_encrypt_to_recipients, process_encrypt_with_passphrase,
+            process_gen_key, process_sign, process_sign_detached, process_sign_minisign,
+            process_verify, process_verify_detached, process_verify_minisign,
+        },
     },
+    utils::{prompt_passphrase, AtomicWriter, CliRng},
     CmdExector,
 };
 
@@ -22,10 +34,14 @@ pub enum TextSubCommand {
     Verify(TextVerifyOpts),
     #[command(about = "生成密钥")]
     Generate(TextKeyGenerateOpts),
-    #[command(about = "ChaCha20Poly1305 加密消息")]
+    #[command(about = "ChaCha20Poly1305 或 age (X25519) 加密消息")]
     Encrypt(TextEncryptOpts),
-    #[command(about = "ChaCha20Poly1305 解密消息")]
+    #[command(about = "ChaCha20Poly1305 或 age (X25519) 解密消息")]
     Decrypt(TextDecryptOpts),
+    #[command(about = "为目录下的所有文件生成签名清单")]
+    ManifestSign(TextManifestSignOpts),
+    #[command(about = "验证签名清单并列出发生变化的文件")]
+    ManifestVerify(TextManifestVerifyOpts),
 }
 
 // impl CmdExector for TextSubCommand {
@@ -48,12 +64,51 @@ pub struct TextSignOpts {
     pub key: String,
     #[arg(long, value_parser = parse_format, default_value = "blake3")]
     pub format: TextSignFormat,
+    /// 写入分离式签名文件（如 `<文件>.sig`），记录算法、密钥 ID 和签名
+    #[arg(short, long)]
+    pub output: Option<String>,
+    /// 输出 minisign 兼容的签名（仅 ed25519），可用 `minisign -V` 验证
+    #[arg(long)]
+    pub minisign: bool,
+    /// minisign 签名中受保护的注释，默认记录时间戳和文件名
+    #[arg(short, long, requires = "minisign")]
+    pub trusted_comment: Option<String>,
+    /// 不预哈希，兼容 minisign 0.10 之前的版本
+    #[arg(long, requires = "minisign")]
+    pub legacy: bool,
 }
 
 impl CmdExector for TextSignOpts {
     async fn execute(&self) -> anyhow::Result<()> {
-        let signed = process_sign(&self.input, &self.key, self.format)?;
-        println!("{}", signed);
+        if self.minisign {
+            if !matches!(
+                self.format,
+                TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph
+            ) {
+                anyhow::bail!("minisign 签名仅支持 ed25519");
+            }
+            let minisig = process_sign_minisign(
+                &self.input,
+                &self.key,
+                !self.legacy,
+                self.trusted_comment.as_deref(),
+            )?;
+            let mut writer = AtomicWriter::new(self.output.as_deref().unwrap_or("-"))?;
+            write!(writer, "{}", minisig)?;
+            return writer.commit();
+        }
+        match &self.output {
+            Some(output) => {
+                let detached = process_sign_detached(&self.input, &self.key, self.format)?;
+                let mut writer = AtomicWriter::new(output)?;
+                writeln!(writer, "{}", detached)?;
+                writer.commit()?;
+            }
+            None => {
+                let signed = process_sign(&self.input, &self.key, self.format)?;
+                println!("{}", signed);
+            }
+        }
         Ok(())
     }
 }
@@ -64,62 +119,271 @@ pub struct TextVerifyOpts {
     pub input: String,
     #[arg(short, long, value_parser = verify_file)]
     pub key: String,
-    #[arg(long, value_parser = parse_format, default_value = "blake3")]
-    pub format: TextSignFormat,
-    #[arg(short, long)]
-    pub sig: String,
+    /// 签名算法，默认 blake3；使用 `--sig-file` 时必须显式指定
+    #[arg(long, value_parser = parse_format)]
+    pub format: Option<TextSignFormat>,
+    #[arg(short, long, required_unless_present_any = ["sig_file", "minisig"])]
+    pub sig: Option<String>,
+    /// 分离式签名文件，其中记录的算法必须与 `--format` 一致
+    #[arg(long, value_parser = verify_file, conflicts_with = "sig", requires = "format")]
+    pub sig_file: Option<String>,
+    /// minisign 的 `.minisig` 签名文件，公钥可以是 minisign 格式
+    #[arg(long, value_parser = verify_file, conflicts_with_all = ["sig", "sig_file"])]
+    pub minisig: Option<String>,
 }
 
 impl CmdExector for TextVerifyOpts {
     async fn execute(&self) -> anyhow::Result<()> {
-        let verified = process_verify(&self.input, &self.key, &self.sig, self.format)?;
+        let verified = match (&self.sig, &self.sig_file, &self.minisig) {
+            (Some(sig), _, _) => process_verify(
+                &self.input,
+                &self.key,
+                sig,
+                self.format.unwrap_or(TextSignFormat::Blake3),
+            )?,
+            (None, Some(sig_file), _) => process_verify_detached(
+                &self.input,
+                &self.key,
+                sig_file,
+                self.format
+                    .expect("clap 保证 --sig-file 与 --format 同时出现"),
+            )?,
+            (None, None, Some(minisig)) => {
+                let comment = process_verify_minisign(&self.input, &self.key, minisig)?;
+                if let Some(comment) = &comment {
+                    println!("trusted comment: {}", comment);
+                }
+                comment.is_some()
+            }
+            (None, None, None) => unreachable!("clap 保证至少指定一种签名"),
+        };
         println!("{}", verified);
         Ok(())
     }
 }
 
 #[derive(Debug, Parser)]
-pub struct TextKeyGenerateOpts {
-    #[arg(long, value_parser = parse_format, default_value = "blake3")]
+pub struct TextManifestSignOpts {
+    #[arg(short, long, value_parser = verify_path)]
+    pub dir: PathBuf,
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: String,
+    #[arg(long, value_parser = parse_format, default_value = "ed25519")]
     pub format: TextSignFormat,
+    /// 清单文件路径；位于目录内时不会计入清单
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+}
+
+impl CmdExector for TextManifestSignOpts {
+    async fn execute(&self) -> anyhow::Result<()> {
+        let exclude = (self.output != "-").then(|| Path::new(&self.output));
+        let manifest = process_manifest_sign(&self.dir, &self.key, self.format, exclude)?;
+        let mut writer = AtomicWriter::new(&self.output)?;
+        writeln!(writer, "{}", manifest)?;
+        writer.commit()
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct TextManifestVerifyOpts {
+    #[arg(short, long, value_parser = verify_path)]
+    pub dir: PathBuf,
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: String,
+    /// 清单的签名算法，必须与清单中记录的一致
+    #[arg(long, value_parser = parse_format, default_value = "ed25519")]
+    pub format: TextSignFormat,
+    #[arg(short, long, value_parser = verify_file)]
+    pub manifest: String,
+}
+
+impl CmdExector for TextManifestVerifyOpts {
+    async fn execute(&self) -> anyhow::Result<()> {
+        let report =
+            process_manifest_verify(&self.dir, &self.key, Path::new(&self.manifest), self.format)?;
+        for path in &report.changed {
+            println!("已修改: {}", path);
+        }
+        for path in &report.missing {
+            println!("已删除: {}", path);
+        }
+        for path in &report.added {
+            println!("新增: {}", path);
+        }
+        println!("{}", report.is_clean());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct TextKeyGenerateOpts {
+    #[arg(long, value_parser = parse_key_kind, default_value = "blake3")]
+    pub format: TextKeyKind,
     #[arg(short, long, value_parser = verify_path)]
     pub output: PathBuf,
+    /// ed25519 密钥文件格式：raw、pem（PKCS#8/SPKI）、openssh、jwk 或 minisign
+    #[arg(long, value_parser = parse_key_format, default_value = "raw")]
+    pub key_format: KeyFormat,
+    /// 在终端输入口令加密保存私钥：pem、openssh、minisign 使用各自的标准加密方式，
+    /// raw 使用 Argon2id 派生的密钥（只能由 rcli 读取），jwk 不支持
+    #[arg(short, long)]
+    pub passphrase: bool,
+    /// 覆盖已存在的密钥文件
+    #[arg(long)]
+    pub force: bool,
 }
 
 impl CmdExector for TextKeyGenerateOpts {
     async fn execute(&self) -> anyhow::Result<()> {
-        let key = process_gen_key(self.format)?;
-        match self.format {
-            TextSignFormat::Blake3 => {
-                let name = self.output.join("blake3");
-                fs::write(name, &key[0]).await?;
+        let mut rng = CliRng::os();
+        // 第一个文件是私钥，第二个（如果有）是公钥
+        let names = match self.format {
+            TextKeyKind::Sign(TextSignFormat::Blake3) => vec!["blake3"],
+            TextKeyKind::Sign(TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph) => {
+                match self.key_format {
+                    KeyFormat::Raw => vec!["ed25519.sk", "ed25519.pk"],
+                    KeyFormat::Pem => vec!["ed25519.pem", "ed25519.pub.pem"],
+                    KeyFormat::OpenSsh => vec!["id_ed25519", "id_ed25519.pub"],
+                    KeyFormat::Jwk => vec!["ed25519.jwk", "ed25519.pub.jwk"],
+                    KeyFormat::Minisign => vec!["minisign.key", "minisign.pub"],
+                }
             }
-            TextSignFormat::Ed25519 => {
-                fs::write(self.output.join("ed25519.sk"), &key[0]).await?;
-                fs::write(self.output.join("ed25519.pk"), &key[1]).await?;
+            TextKeyKind::Sign(TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512) => {
+                vec!["hmac.key"]
             }
-            TextSignFormat::ChaCha20Poly1305 => {
-                let name = self.output.join("chacha20poly1305.key");
-                fs::write(name, &key[0]).await?;
+            TextKeyKind::Cipher(AeadCipher::ChaCha20Poly1305) => vec!["chacha20poly1305.key"],
+            TextKeyKind::Cipher(AeadCipher::XChaCha20Poly1305) => vec!["xchacha20poly1305.key"],
+            TextKeyKind::Cipher(AeadCipher::Aes256Gcm) => vec!["aes-256-gcm.key"],
+            TextKeyKind::Cipher(AeadCipher::Aes256GcmSiv) => vec!["aes-256-gcm-siv.key"],
+            TextKeyKind::X25519 => vec!["x25519.key", "x25519.pub"],
+        };
+        let paths: Vec<PathBuf> = names.iter().map(|name| self.output.join(name)).collect();
+        if !self.force {
+            if let Some(path) = paths.iter().find(|path| path.exists()) {
+                anyhow::bail!("密钥文件 {} 已存在，使用 --force 覆盖", path.display());
             }
+        }
+
+        if self.passphrase && self.key_format == KeyFormat::Jwk {
+            anyhow::bail!("jwk 格式没有标准的私钥加密方式，不能与 --passphrase 同时使用");
+        }
+        let passphrase = if self.passphrase {
+            Some(prompt_passphrase(true)?)
+        } else {
+            None
         };
+        let key = process_gen_key(
+            self.format,
+            self.key_format,
+            passphrase.as_deref(),
+            &mut rng,
+        )?;
+        for (i, (path, data)) in paths.iter().zip(&key).enumerate() {
+            write_key_file(path, data, i == 0, self.force).await?;
+        }
         Ok(())
     }
 }
 
+// 私钥文件创建时即为 0600，避免写入过程中被其他用户读取
+async fn write_key_file(path: &Path, data: &[u8], secret: bool, force: bool) -> anyhow::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    if secret {
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).await.map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            anyhow::anyhow!("密钥文件 {} 已存在，使用 --force 覆盖", path.display())
+        }
+        _ => e.into(),
+    })?;
+    // mode 只在新建文件时生效，覆盖已有文件时需要单独收紧权限
+    #[cfg(unix)]
+    if secret {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .await?;
+    }
+    file.write_all(data).await?;
+    file.flush().await?;
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 pub struct TextEncryptOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
-    #[arg(short, long, value_parser = verify_file)]
-    pub key: String,
+    #[arg(short, long, value_parser = verify_file, required_unless_present_any = ["passphrase", "recipient"])]
+    pub key: Option<String>,
+    /// 在终端输入口令，使用 Argon2id 派生密钥
+    #[arg(short, long, conflicts_with_all = ["key", "recipient"])]
+    pub passphrase: bool,
+    /// 接收者的 age 公钥（`age1...`）或公钥文件，可重复指定；输出为 age 格式
+    #[arg(short, long, conflicts_with = "key")]
+    pub recipient: Vec<String>,
+    /// 对称加密算法，记录在密文头部，解密时自动识别
+    #[arg(long, value_parser = parse_cipher, default_value = "chacha20poly1305", conflicts_with = "recipient")]
+    pub cipher: AeadCipher,
+    /// 附加数据，只参与认证而不写入密文，解密时必须一致；`@路径` 表示读取文件内容
+    #[arg(long, conflicts_with = "recipient")]
+    pub aad: Option<String>,
+    /// 密文写入的文件，默认输出到标准输出
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    /// 输出原始二进制密文而不是 base64 文本
+    #[arg(long)]
+    pub binary: bool,
 }
 
 impl CmdExector for TextEncryptOpts {
     async fn execute(&self) -> anyhow::Result<()> {
-        let encrypted = process_encrypt(&self.input, &self.key)?;
-        println!("{}", encrypted);
-        Ok(())
+        let mut rng = CliRng::os();
+        if !self.recipient.is_empty() {
+            let mut writer = AtomicWriter::new(&self.output)?;
+            process_encrypt_to_recipients(
+                &self.input,
+                &mut writer,
+                &self.recipient,
+                self.binary,
+                &mut rng,
+            )?;
+            return writer.commit();
+        }
+        let aad = read_aad(self.aad.as_deref())?;
+        // 输出只在加密成功后才替换目标文件
+        let mut writer = AtomicWriter::new(&self.output)?;
+        match &self.key {
+            Some(key) => process_encrypt(
+                &self.input,
+                &mut writer,
+                key,
+                self.cipher,
+                &aad,
+                self.binary,
+                &mut rng,
+            )?,
+            None => {
+                let passphrase = prompt_passphrase(true)?;
+                process_encrypt_with_passphrase(
+                    &self.input,
+                    &mut writer,
+                    &passphrase,
+                    self.cipher,
+                    &aad,
+                    self.binary,
+                    &mut rng,
+                )?
+            }
+        }
+        writer.commit()
     }
 }
 
@@ -127,15 +391,61 @@ impl CmdExector for TextEncryptOpts {
 pub struct TextDecryptOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
-    #[arg(short, long, value_parser = verify_file)]
-    pub key: String,
+    #[arg(short, long, value_parser = verify_file, required_unless_present_any = ["passphrase", "identity"])]
+    pub key: Option<String>,
+    /// 在终端输入口令，使用 Argon2id 派生密钥
+    #[arg(short, long, conflicts_with_all = ["key", "identity"])]
+    pub passphrase: bool,
+    /// age 私钥文件，可重复指定；用于解密 age 格式的文件
+    #[arg(long, value_parser = verify_file, conflicts_with = "key")]
+    pub identity: Vec<String>,
+    /// 附加数据，只参与认证而不写入密文，解密时必须一致；`@路径` 表示读取文件内容
+    #[arg(long, conflicts_with = "identity")]
+    pub aad: Option<String>,
+    /// 明文写入的文件，默认原样写到标准输出
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    /// 输入为原始二进制密文而不是 base64 文本，age 格式会自动识别
+    #[arg(long)]
+    pub binary: bool,
 }
 
 impl CmdExector for TextDecryptOpts {
     async fn execute(&self) -> anyhow::Result<()> {
-        let decrypted = process_decrypt(&self.input, &self.key)?;
-        println!("{}", decrypted);
-        Ok(())
+        if !self.identity.is_empty() {
+            let mut writer = AtomicWriter::new(&self.output)?;
+            process_decrypt_with_identities(&self.input, &mut writer, &self.identity)?;
+            return writer.commit();
+        }
+        let aad = read_aad(self.aad.as_deref())?;
+        // 解密失败时不会留下空的或不完整的明文文件
+        let mut writer = AtomicWriter::new(&self.output)?;
+        match &self.key {
+            Some(key) => process_decrypt(&self.input, &mut writer, key, &aad, self.binary)?,
+            None => {
+                let passphrase = prompt_passphrase(false)?;
+                process_decrypt_with_passphrase(
+                    &self.input,
+                    &mut writer,
+                    &passphrase,
+                    &aad,
+                    self.binary,
+                )?
+            }
+        }
+        writer.commit()
+    }
+}
+
+// `@路径` 读取文件内容，否则直接使用字符串本身
+fn read_aad(value: Option<&str>) -> anyhow::Result<Vec<u8>> {
+    match value {
+        None => Ok(Vec::new()),
+        Some(value) => match value.strip_prefix('@') {
+            Some(path) => std::fs::read(path)
+                .map_err(|e| anyhow::anyhow!("无法读取附加数据文件 {}: {}", path, e)),
+            None => Ok(value.as_bytes().to_vec()),
+        },
     }
 }
 
@@ -143,13 +453,28 @@ impl CmdExector for TextDecryptOpts {
 pub enum TextSignFormat {
     Blake3,
     Ed25519,
-    ChaCha20Poly1305,
+    /// 预哈希的 Ed25519，流式处理大文件
+    Ed25519ph,
+    HmacSha256,
+    HmacSha512,
 }
 
 fn parse_format(value: &str) -> anyhow::Result<TextSignFormat> {
     value.parse()
 }
 
+impl From<TextSignFormat> for &'static str {
+    fn from(value: TextSignFormat) -> Self {
+        match value {
+            TextSignFormat::Blake3 => "blake3",
+            TextSignFormat::Ed25519 => "ed25519",
+            TextSignFormat::Ed25519ph => "ed25519ph",
+            TextSignFormat::HmacSha256 => "hmac-sha256",
+            TextSignFormat::HmacSha512 => "hmac-sha512",
+        }
+    }
+}
+
 impl FromStr for TextSignFormat {
     type Err = anyhow::Error;
 
@@ -157,8 +482,149 @@ impl FromStr for TextSignFormat {
         match s.to_lowercase().as_str() {
             "blake3" => Ok(TextSignFormat::Blake3),
             "ed25519" => Ok(TextSignFormat::Ed25519),
-            "chacha20poly1305" => Ok(TextSignFormat::ChaCha20Poly1305),
+            "ed25519ph" => Ok(TextSignFormat::Ed25519ph),
+            "hmac-sha256" => Ok(TextSignFormat::HmacSha256),
+            "hmac-sha512" => Ok(TextSignFormat::HmacSha512),
             _ => anyhow::bail!("不支持的格式"),
         }
     }
 }
+
+impl Display for TextSignFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+/// `text generate` 可以生成的密钥：签名密钥、对称加密密钥或 age 的 X25519 密钥对。
+#[derive(Debug, Clone, Copy)]
+pub enum TextKeyKind {
+    Sign(TextSignFormat),
+    Cipher(AeadCipher),
+    /// age 兼容的 X25519 公钥加密
+    X25519,
+}
+
+fn parse_key_kind(value: &str) -> anyhow::Result<TextKeyKind> {
+    value.parse()
+}
+
+impl FromStr for TextKeyKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("x25519") {
+            return Ok(TextKeyKind::X25519);
+        }
+        if let Ok(format) = s.parse() {
+            return Ok(TextKeyKind::Sign(format));
+        }
+        match s.parse() {
+            Ok(cipher) => Ok(TextKeyKind::Cipher(cipher)),
+            Err(_) => anyhow::bail!("不支持的格式"),
+        }
+    }
+}
+
+impl Display for TextKeyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextKeyKind::Sign(format) => write!(f, "{}", format),
+            TextKeyKind::Cipher(cipher) => write!(f, "{}", cipher),
+            TextKeyKind::X25519 => write!(f, "x25519"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AeadCipher {
+    ChaCha20Poly1305,
+    /// 192 位 nonce，更适合随机生成
+    XChaCha20Poly1305,
+    Aes256Gcm,
+    /// 抗 nonce 误用
+    Aes256GcmSiv,
+}
+
+fn parse_cipher(value: &str) -> anyhow::Result<AeadCipher> {
+    value.parse()
+}
+
+impl From<AeadCipher> for &'static str {
+    fn from(value: AeadCipher) -> Self {
+        match value {
+            AeadCipher::ChaCha20Poly1305 => "chacha20poly1305",
+            AeadCipher::XChaCha20Poly1305 => "xchacha20poly1305",
+            AeadCipher::Aes256Gcm => "aes-256-gcm",
+            AeadCipher::Aes256GcmSiv => "aes-256-gcm-siv",
+        }
+    }
+}
+
+impl FromStr for AeadCipher {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chacha20poly1305" => Ok(AeadCipher::ChaCha20Poly1305),
+            "xchacha20poly1305" => Ok(AeadCipher::XChaCha20Poly1305),
+            "aes-256-gcm" => Ok(AeadCipher::Aes256Gcm),
+            "aes-256-gcm-siv" => Ok(AeadCipher::Aes256GcmSiv),
+            _ => anyhow::bail!("不支持的加密算法"),
+        }
+    }
+}
+
+impl Display for AeadCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyFormat {
+    Raw,
+    /// 私钥为 PKCS#8，公钥为 SPKI
+    Pem,
+    OpenSsh,
+    Jwk,
+    /// 与 minisign / signify 兼容
+    Minisign,
+}
+
+fn parse_key_format(value: &str) -> anyhow::Result<KeyFormat> {
+    value.parse()
+}
+
+impl From<KeyFormat> for &'static str {
+    fn from(value: KeyFormat) -> Self {
+        match value {
+            KeyFormat::Raw => "raw",
+            KeyFormat::Pem => "pem",
+            KeyFormat::OpenSsh => "openssh",
+            KeyFormat::Jwk => "jwk",
+            KeyFormat::Minisign => "minisign",
+        }
+    }
+}
+
+impl FromStr for KeyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(KeyFormat::Raw),
+            "pem" => Ok(KeyFormat::Pem),
+            "openssh" => Ok(KeyFormat::OpenSsh),
+            "jwk" => Ok(KeyFormat::Jwk),
+            "minisign" => Ok(KeyFormat::Minisign),
+            _ => anyhow::bail!("不支持的密钥文件格式"),
+        }
+    }
+}
+
+impl Display for KeyFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -8,8 +8,10 @@ use cli::{
     base64::*,
     csv::*,
     gen_pass::*,
+    hash::*,
     http::{self, *},
     jwt::{self, *},
+    otp::{self, *},
     text::{self, *},
     SubCommand,
 };
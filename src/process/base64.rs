@@ -1,49 +1,381 @@
-use std::io::Read;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
 
 use crate::{cli::base64::Base64Format, utils::get_reader};
 use base64::{
-    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
-    Engine as _,
+    alphabet,
+    engine::{
+        general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+        DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig,
+    },
+    read::DecoderReader,
+    write::EncoderWriter,
 };
-pub fn process_encode(input: &str, format: Base64Format) -> anyhow::Result<String> {
-    let buf = read_data(input)?;
+use percent_encoding::percent_decode;
 
-    let encodeed = match format {
-        Base64Format::Standard => STANDARD.encode(&buf),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.encode(&buf),
-    };
-    Ok(encodeed)
+// 流式处理时每次从输入读取的字节数，内存占用与输入大小无关
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// 宽松解码：URL 安全字符会先被替换成标准字符，填充可有可无
+const LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+// 识别 MIME 类型时最多预读的字节数
+const SNIFF_SIZE: usize = 8 * 1024;
+// data URI 头部（`data:` 与 `,` 之间）的最大长度
+const MAX_DATA_URI_HEADER: u64 = 1024;
+
+/// data URI 头部的信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataUri {
+    pub mime: String,
+    pub base64: bool,
 }
 
-pub fn process_decode(input: &str, format: Base64Format) -> anyhow::Result<Vec<u8>> {
-    let buf = read_data(input)?;
+impl DataUri {
+    /// 根据 MIME 类型推断文件扩展名，无法推断时为 `bin`。
+    pub fn extension(&self) -> String {
+        let mime = self.mime.as_str();
+        match mime {
+            "text/plain" => return "txt".to_string(),
+            "image/jpeg" => return "jpg".to_string(),
+            _ => {}
+        }
+        let Some(exts) = mime_guess::get_mime_extensions_str(mime) else {
+            return "bin".to_string();
+        };
+        // 优先使用与子类型同名的扩展名，如 image/png -> png
+        let subtype = mime.split('/').nth(1).unwrap_or_default();
+        exts.iter()
+            .find(|ext| **ext == subtype)
+            .or(exts.first())
+            .map_or_else(|| "bin".to_string(), |ext| ext.to_string())
+    }
+}
 
-    let decodeed = match format {
-        Base64Format::Standard => STANDARD.decode(&buf),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.decode(&buf),
-    }?;
-    // TODO: 解码后的数据可能不是字符串（但在这个例子中，我们假设)
-    // let decodeed = String::from_utf8(decodeed)?;
-    Ok(decodeed)
+pub fn process_encode(
+    input: &str,
+    writer: &mut dyn Write,
+    format: Base64Format,
+) -> anyhow::Result<()> {
+    let mut reader = get_reader(input)?;
+    encode_stream(&mut reader, writer, format)
 }
 
-fn read_data(input: &str) -> anyhow::Result<Vec<u8>> {
+/// 编码为 `data:<mime>;base64,...`，MIME 类型先按魔数识别，再按扩展名推断。
+pub fn process_encode_data_uri(input: &str, writer: &mut dyn Write) -> anyhow::Result<()> {
     let mut reader = get_reader(input)?;
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-    let buf = buf.trim_ascii().to_vec();
-    Ok(buf)
+    let mut head = Vec::with_capacity(SNIFF_SIZE);
+    reader
+        .by_ref()
+        .take(SNIFF_SIZE as u64)
+        .read_to_end(&mut head)?;
+    let mime = detect_mime(input, &head);
+    write!(writer, "data:{};base64,", mime)?;
+    let mut reader = Cursor::new(head).chain(reader);
+    encode_stream(&mut reader, writer, Base64Format::Standard)
+}
+
+pub fn process_decode(
+    input: &str,
+    writer: &mut dyn Write,
+    format: Base64Format,
+) -> anyhow::Result<()> {
+    let (mut reader, data_uri) = open_decode_input(input)?;
+    decode_payload(&mut reader, writer, format, data_uri.as_ref())
+}
+
+/// 打开待解码的输入；如果内容是 data URI，会先读掉 `data:...,` 头部并返回其信息。
+pub fn open_decode_input(input: &str) -> anyhow::Result<(Box<dyn BufRead>, Option<DataUri>)> {
+    let reader: Box<dyn BufRead> = Box::new(BufReader::new(get_reader(input)?));
+    read_data_uri_header(reader)
+}
+
+/// 解码 `open_decode_input` 返回的内容，data URI 会忽略 `format`。
+pub fn decode_payload(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+    data_uri: Option<&DataUri>,
+) -> anyhow::Result<()> {
+    match data_uri {
+        Some(uri) if !uri.base64 => percent_decode_stream(reader, writer),
+        Some(_) => decode_stream(reader, writer, Base64Format::Lenient),
+        None => decode_stream(reader, writer, format),
+    }
+}
+
+// 跳过开头的空白后识别 `data:` 前缀；一次 fill_buf 未必能读到完整的前缀，
+// 因此循环读取，不是 data URI 时把已读的前缀接回到返回的 reader 前面
+fn read_data_uri_header(
+    mut reader: Box<dyn BufRead>,
+) -> anyhow::Result<(Box<dyn BufRead>, Option<DataUri>)> {
+    const PREFIX: &[u8] = b"data:";
+    loop {
+        let buf = reader.fill_buf()?;
+        let whitespace = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        if whitespace == 0 {
+            break;
+        }
+        reader.consume(whitespace);
+    }
+    let mut prefix = Vec::with_capacity(PREFIX.len());
+    while prefix.len() < PREFIX.len() {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let n = buf.len().min(PREFIX.len() - prefix.len());
+        prefix.extend_from_slice(&buf[..n]);
+        reader.consume(n);
+    }
+    if !prefix.eq_ignore_ascii_case(PREFIX) {
+        return Ok((Box::new(Cursor::new(prefix).chain(reader)), None));
+    }
+
+    let mut header = Vec::new();
+    (&mut reader)
+        .take(MAX_DATA_URI_HEADER)
+        .read_until(b',', &mut header)?;
+    if header.pop() != Some(b',') {
+        anyhow::bail!("data URI 缺少 ',' 分隔符");
+    }
+    let header = String::from_utf8(header)?;
+    let mut params = header.split(';');
+    // RFC 2397：省略 MIME 类型时默认为 text/plain
+    let mime = match params.next() {
+        Some(mime) if !mime.is_empty() => mime.to_lowercase(),
+        _ => "text/plain".to_string(),
+    };
+    let base64 = params.any(|p| p.eq_ignore_ascii_case("base64"));
+    Ok((reader, Some(DataUri { mime, base64 })))
+}
+
+fn detect_mime(input: &str, head: &[u8]) -> String {
+    if let Some(kind) = infer::get(head) {
+        return kind.mime_type().to_string();
+    }
+    if input != "-" {
+        if let Some(mime) = mime_guess::from_path(input).first_raw() {
+            return mime.to_string();
+        }
+    }
+    // 预读可能截断多字节字符，只要错误出现在结尾就仍按文本处理
+    match std::str::from_utf8(head) {
+        Ok(_) => "text/plain".to_string(),
+        Err(e) if e.error_len().is_none() => "text/plain".to_string(),
+        Err(_) => "application/octet-stream".to_string(),
+    }
+}
+
+fn engine(format: Base64Format) -> &'static GeneralPurpose {
+    match format {
+        Base64Format::Standard | Base64Format::Mime | Base64Format::Pem => &STANDARD,
+        Base64Format::StandardNoPad => &STANDARD_NO_PAD,
+        Base64Format::UrlSafe => &URL_SAFE_NO_PAD,
+        Base64Format::UrlSafePad => &URL_SAFE,
+        Base64Format::Lenient => &LENIENT,
+    }
+}
+
+// 编码时的折行宽度和换行符
+fn line_wrap(format: Base64Format) -> Option<(usize, &'static [u8])> {
+    match format {
+        Base64Format::Mime => Some((76, b"\r\n")),
+        Base64Format::Pem => Some((64, b"\n")),
+        _ => None,
+    }
+}
+
+/// 分块编码：`EncoderWriter` 内部会把不足 3 字节的尾巴留到下一块，直到 `finish` 才输出填充。
+pub fn encode_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+) -> anyhow::Result<()> {
+    if matches!(format, Base64Format::Lenient) {
+        anyhow::bail!("lenient 格式只能用于解码");
+    }
+    let writer = LineWrap::new(writer, line_wrap(format));
+    let mut encoder = EncoderWriter::new(writer, engine(format));
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        encoder.write_all(&buf[..n])?;
+    }
+    encoder.finish()?.finish()?;
+    Ok(())
+}
+
+/// 分块解码：先剔除换行等空白，`DecoderReader` 再按 4 字符一组跨块拼接解码。
+pub fn decode_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+) -> anyhow::Result<()> {
+    let lenient = matches!(format, Base64Format::Lenient);
+    let reader = SkipWhitespace::new(reader, lenient);
+    let mut decoder = DecoderReader::new(reader, engine(format));
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = decoder.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// 分块解码百分号编码的正文并去掉首尾空白；块末尾未完整的 `%XX` 和空白留到下一块再处理
+fn percent_decode_stream(reader: &mut dyn Read, writer: &mut dyn Write) -> anyhow::Result<()> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut pending = Vec::new();
+    let mut started = false;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        let mut chunk = &buf[..n];
+        if !started {
+            chunk = chunk.trim_ascii_start();
+            started = !chunk.is_empty();
+        }
+        pending.extend_from_slice(chunk);
+        let mut ready = pending.trim_ascii_end().len();
+        if let Some(pos) = pending[..ready].iter().rposition(|&b| b == b'%') {
+            if ready - pos < 3 {
+                ready = pos;
+            }
+        }
+        let decoded = percent_decode(&pending[..ready]).collect::<Vec<_>>();
+        writer.write_all(&decoded)?;
+        pending.drain(..ready);
+    }
+    let decoded = percent_decode(pending.trim_ascii_end()).collect::<Vec<_>>();
+    writer.write_all(&decoded)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 返回一个边读边解码的 reader，同样会忽略输入中的空白。
+pub fn decode_reader<'a, R: Read + 'a>(reader: R, format: Base64Format) -> impl Read + 'a {
+    let lenient = matches!(format, Base64Format::Lenient);
+    DecoderReader::new(SkipWhitespace::new(reader, lenient), engine(format))
+}
+
+/// 按固定宽度插入换行符，最后一行之后同样追加换行符。
+struct LineWrap<W> {
+    inner: W,
+    wrap: Option<(usize, &'static [u8])>,
+    col: usize,
+}
+
+impl<W: Write> LineWrap<W> {
+    fn new(inner: W, wrap: Option<(usize, &'static [u8])>) -> Self {
+        Self {
+            inner,
+            wrap,
+            col: 0,
+        }
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        if let Some((_, ending)) = self.wrap {
+            if self.col > 0 {
+                self.inner.write_all(ending)?;
+            }
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for LineWrap<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some((width, ending)) = self.wrap else {
+            return self.inner.write(buf);
+        };
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.col == width {
+                self.inner.write_all(ending)?;
+                self.col = 0;
+            }
+            let n = rest.len().min(width - self.col);
+            self.inner.write_all(&rest[..n])?;
+            self.col += n;
+            rest = &rest[n..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 读取时丢弃 ASCII 空白，用于解码按行折叠的 base64 文本；
+/// 宽松模式下还会把 URL 安全字母表的 `-`/`_` 换成 `+`/`/`。
+struct SkipWhitespace<R> {
+    inner: R,
+    lenient: bool,
+}
+
+impl<R: Read> SkipWhitespace<R> {
+    fn new(inner: R, lenient: bool) -> Self {
+        Self { inner, lenient }
+    }
+}
+
+impl<R: Read> Read for SkipWhitespace<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut len = 0;
+            for i in 0..n {
+                let c = match buf[i] {
+                    c if c.is_ascii_whitespace() => continue,
+                    b'-' if self.lenient => b'+',
+                    b'_' if self.lenient => b'/',
+                    c => c,
+                };
+                buf[len] = c;
+                len += 1;
+            }
+            // 整块都是空白时继续读，避免把 0 误当作 EOF
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use base64::Engine as _;
+    use rand::RngCore;
+    use std::io::Cursor;
+
+    use crate::utils::{CliRng, TestDir};
+
     #[test]
     fn test_process_encode() {
         let input = "Cargo.toml";
         let format = Base64Format::Standard;
-        let result = process_encode(input, format);
+        let result = process_encode(input, &mut Vec::new(), format);
         assert!(result.is_ok());
     }
 
@@ -51,7 +383,204 @@ mod tests {
     fn test_process_decode() {
         let input = "fixtrues/base64.txt";
         let format = Base64Format::Standard;
-        let result = process_decode(input, format);
+        let result = process_decode(input, &mut Vec::new(), format);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_process_decode_binary_roundtrip() -> anyhow::Result<()> {
+        let input = "fixtrues/ed25519.sk";
+        let mut encoded = Vec::new();
+        process_encode(input, &mut encoded, Base64Format::Standard)?;
+        let dir = TestDir::new()?;
+        let path = dir.file("roundtrip.txt");
+        std::fs::write(&path, encoded)?;
+        let mut decoded = Vec::new();
+        process_decode(&path, &mut decoded, Base64Format::Standard)?;
+        assert_eq!(decoded, std::fs::read(input)?);
+        Ok(())
+    }
+
+    fn random_data(len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        CliRng::insecure_from_seed(42).fill_bytes(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_stream_larger_than_chunk() -> anyhow::Result<()> {
+        // 长度不是 3 的倍数，且跨越多个块
+        for len in [CHUNK_SIZE * 3 + 1, CHUNK_SIZE * 2 + 2] {
+            let data = random_data(len);
+            for format in [Base64Format::Standard, Base64Format::UrlSafe] {
+                let mut encoded = Vec::new();
+                encode_stream(&mut Cursor::new(&data), &mut encoded, format)?;
+                assert_eq!(encoded, engine(format).encode(&data).into_bytes());
+
+                let mut decoded = Vec::new();
+                decode_stream(&mut Cursor::new(&encoded), &mut decoded, format)?;
+                assert_eq!(decoded, data);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_stream_line_wrapped() -> anyhow::Result<()> {
+        let data = random_data(CHUNK_SIZE * 2 + 1);
+        let encoded = STANDARD.encode(&data);
+        let wrapped = encoded
+            .as_bytes()
+            .chunks(76)
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+            .collect::<Vec<_>>()
+            .join("\r\n")
+            + "\n";
+        let mut decoded = Vec::new();
+        decode_stream(
+            &mut Cursor::new(wrapped),
+            &mut decoded,
+            Base64Format::Standard,
+        )?;
+        assert_eq!(decoded, data);
+
+        assert!(decode_stream(
+            &mut Cursor::new("aGVs*G8="),
+            &mut Vec::new(),
+            Base64Format::Standard
+        )
+        .is_err());
+        Ok(())
+    }
+
+    fn encode(data: &[u8], format: Base64Format) -> anyhow::Result<String> {
+        let mut encoded = Vec::new();
+        encode_stream(&mut Cursor::new(data), &mut encoded, format)?;
+        Ok(String::from_utf8(encoded)?)
+    }
+
+    fn decode(data: &str, format: Base64Format) -> anyhow::Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        decode_stream(&mut Cursor::new(data), &mut decoded, format)?;
+        Ok(decoded)
+    }
+
+    #[test]
+    fn test_format_matrix() -> anyhow::Result<()> {
+        let data = [0xfb, 0xff, 0xbf, 0x61];
+        let cases = [
+            (Base64Format::Standard, "+/+/YQ=="),
+            (Base64Format::StandardNoPad, "+/+/YQ"),
+            (Base64Format::UrlSafe, "-_-_YQ"),
+            (Base64Format::UrlSafePad, "-_-_YQ=="),
+            (Base64Format::Mime, "+/+/YQ==\r\n"),
+            (Base64Format::Pem, "+/+/YQ==\n"),
+        ];
+        for (format, expected) in cases {
+            assert_eq!(encode(&data, format)?, expected);
+            assert_eq!(decode(expected, format)?, data);
+        }
+        assert!(decode("+/+/YQ", Base64Format::Standard).is_err());
+        assert!(decode("+/+/YQ==", Base64Format::StandardNoPad).is_err());
+        assert!(encode(&data, Base64Format::Lenient).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_wrapping() -> anyhow::Result<()> {
+        let data = random_data(1000);
+        let mime = encode(&data, Base64Format::Mime)?;
+        let lines = mime.split_terminator("\r\n").collect::<Vec<_>>();
+        assert!(lines[..lines.len() - 1].iter().all(|l| l.len() == 76));
+        assert!(mime.ends_with("\r\n"));
+        assert_eq!(lines.concat(), STANDARD.encode(&data));
+
+        let pem = encode(&data[..48], Base64Format::Pem)?;
+        assert_eq!(pem, format!("{}\n", STANDARD.encode(&data[..48])));
+        assert_eq!(decode(&pem, Base64Format::Pem)?, &data[..48]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lenient_decode() -> anyhow::Result<()> {
+        let data = [0xfb, 0xff, 0xbf, 0x61];
+        for input in ["+/+/YQ==", "-_-_YQ", " -_+/\nYQ=\n", "+/+/\r\nYQ"] {
+            assert_eq!(decode(input, Base64Format::Lenient)?, data);
+        }
+        assert!(decode("+/+/Y*Q", Base64Format::Lenient).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_data_uri() -> anyhow::Result<()> {
+        let dir = TestDir::new()?;
+        let png = dir.file("image");
+        std::fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")?;
+        let mut encoded = Vec::new();
+        process_encode_data_uri(&png, &mut encoded)?;
+        let encoded = String::from_utf8(encoded)?;
+        assert_eq!(encoded, "data:image/png;base64,iVBORw0KGgoAAAANSUhEUg==");
+
+        let mut encoded = Vec::new();
+        process_encode_data_uri("Cargo.toml", &mut encoded)?;
+        assert!(encoded.starts_with(b"data:text/x-toml;base64,"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_data_uri() -> anyhow::Result<()> {
+        let cases = [
+            (
+                "data:image/png;base64,iVBORw0KGgo=",
+                "image/png",
+                "png",
+                &b"\x89PNG\r\n\x1a\n"[..],
+            ),
+            ("data:,hello%20world", "text/plain", "txt", b"hello world"),
+            (
+                "data:application/json;charset=utf-8;base64,e30",
+                "application/json",
+                "json",
+                b"{}",
+            ),
+            ("data:foo/bar;base64,AA==", "foo/bar", "bin", b"\0"),
+        ];
+        for (uri, mime, ext, data) in cases {
+            let (mut reader, data_uri) = read_data_uri_header(Box::new(Cursor::new(uri)))?;
+            let data_uri = data_uri.unwrap();
+            assert_eq!(data_uri.mime, mime);
+            assert_eq!(data_uri.extension(), ext);
+            let mut decoded = Vec::new();
+            decode_payload(
+                &mut reader,
+                &mut decoded,
+                Base64Format::Standard,
+                Some(&data_uri),
+            )?;
+            assert_eq!(decoded, data);
+        }
+
+        // 不是 data URI 时，已读取的前缀不会丢失
+        let (mut reader, data_uri) = read_data_uri_header(Box::new(Cursor::new("aGk=")))?;
+        assert_eq!(data_uri, None);
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest)?;
+        assert_eq!(rest, "aGk=");
+        assert!(read_data_uri_header(Box::new(Cursor::new("data:text/plain"))).is_err());
+
+        // 前导空白，以及前缀被拆分在多次读取中
+        let input = Cursor::new("\n  data:,hi");
+        let (_, data_uri) = read_data_uri_header(Box::new(BufReader::with_capacity(2, input)))?;
+        assert_eq!(data_uri.unwrap().mime, "text/plain");
+
+        // 百分号编码的正文按块解码，转义序列可能被拆分在两块之间
+        let mut input = Cursor::new(" \nhello%2")
+            .chain(Cursor::new("0big "))
+            .chain(Cursor::new("world%"))
+            .chain(Cursor::new("21\n"));
+        let mut decoded = Vec::new();
+        percent_decode_stream(&mut input, &mut decoded)?;
+        assert_eq!(decoded, b"hello big world!");
+        Ok(())
+    }
 }
@@ -0,0 +1,457 @@
+use std::io::{Read, Write};
+
+use data_encoding::{
+    Encoding as DataEncoding, Specification, BASE32, BASE32_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE,
+    HEXUPPER,
+};
+use percent_encoding::{percent_decode, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::{
+    cli::base64::Encoding,
+    process::base64::{decode_stream, encode_stream},
+    utils::get_reader,
+};
+
+// RFC 3986 中的非保留字符不编码
+const PERCENT_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+// 4 和 5 的公倍数，base85 与 base32 的分组不会被拆在两块之间
+const CHUNK_SIZE: usize = 60 * 1024;
+
+static Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// 编码输入并写入 `writer`（不含结尾换行）；`separator` 只对 hex 生效，由调用方检查（见 [`Encoding::check_separator`]）。
+pub fn process_encoding_encode(
+    input: &str,
+    writer: &mut dyn Write,
+    encoding: Encoding,
+    separator: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut reader = get_reader(input)?;
+    let reader = &mut *reader;
+    match encoding {
+        Encoding::Base64(format) => return encode_stream(reader, writer, format),
+        Encoding::Hex => encode_hex(reader, writer, &HEXLOWER, separator)?,
+        Encoding::HexUpper => encode_hex(reader, writer, &HEXUPPER, separator)?,
+        Encoding::Base32 => encode_chunks(reader, writer, |chunk| Ok(BASE32.encode(chunk)))?,
+        Encoding::Base32Crockford => {
+            let crockford = crockford()?;
+            encode_chunks(reader, writer, |chunk| Ok(crockford.encode(chunk)))?
+        }
+        Encoding::Ascii85 => encode_chunks(reader, writer, |chunk| Ok(ascii85_encode(chunk)))?,
+        Encoding::Z85 => encode_chunks(reader, writer, z85_encode)?,
+        Encoding::Percent => encode_chunks(reader, writer, |chunk| {
+            Ok(percent_encode(chunk, PERCENT_ENCODE_SET).to_string())
+        })?,
+        // base58 是整体的进制转换，无法分块
+        Encoding::Base58 => {
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf)?;
+            writer.write_all(bs58::encode(buf).into_string().as_bytes())?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 解码输入并把原始字节写入 `writer`，首尾空白会被忽略。
+pub fn process_encoding_decode(
+    input: &str,
+    writer: &mut dyn Write,
+    encoding: Encoding,
+    separator: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut reader = get_reader(input)?;
+    decode_reader(&mut reader, writer, encoding, separator)
+}
+
+/// 同 [`process_encoding_decode`]，从已打开的 `reader` 读取。
+pub fn decode_reader(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    encoding: Encoding,
+    separator: Option<&str>,
+) -> anyhow::Result<()> {
+    match encoding {
+        Encoding::Base64(format) => return decode_stream(reader, writer, format),
+        Encoding::Hex | Encoding::HexUpper => {
+            decode_chunks(reader, writer, 2, separator, |digits, _| {
+                HEXLOWER_PERMISSIVE
+                    .decode(digits)
+                    .map_err(|e| anyhow::anyhow!("非法的 hex 输入: {}", e))
+            })?
+        }
+        Encoding::Base32 => decode_chunks(reader, writer, 8, None, |digits, last| {
+            // 只有最后一块可能带填充
+            let digits = digits.to_ascii_uppercase();
+            if last && digits.contains(&b'=') {
+                Ok(BASE32.decode(&digits)?)
+            } else {
+                Ok(BASE32_NOPAD.decode(&digits)?)
+            }
+        })?,
+        Encoding::Base32Crockford => decode_whole(reader, writer, |text| {
+            Ok(crockford()?.decode(strip_whitespace(text).as_bytes())?)
+        })?,
+        Encoding::Base58 => {
+            decode_whole(reader, writer, |text| Ok(bs58::decode(text).into_vec()?))?
+        }
+        Encoding::Ascii85 => decode_whole(reader, writer, |text| {
+            ascii85_decode(&strip_whitespace(text))
+        })?,
+        Encoding::Z85 => decode_whole(reader, writer, |text| z85_decode(&strip_whitespace(text)))?,
+        Encoding::Percent => decode_whole(reader, writer, |text| {
+            Ok(percent_decode(text.as_bytes()).collect())
+        })?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// 按 CHUNK_SIZE 读满一块再编码；除最后一块外每块都是完整的分组，各块的编码结果直接拼接
+fn encode_chunks(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    mut encode: impl FnMut(&[u8]) -> anyhow::Result<String>,
+) -> anyhow::Result<()> {
+    let mut buf = Vec::with_capacity(CHUNK_SIZE);
+    loop {
+        buf.clear();
+        reader.take(CHUNK_SIZE as u64).read_to_end(&mut buf)?;
+        writer.write_all(encode(&buf)?.as_bytes())?;
+        if buf.len() < CHUNK_SIZE {
+            return Ok(());
+        }
+    }
+}
+
+fn encode_hex(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    hex: &DataEncoding,
+    separator: Option<&str>,
+) -> anyhow::Result<()> {
+    let Some(sep) = separator.filter(|sep| !sep.is_empty()) else {
+        return encode_chunks(reader, writer, |chunk| Ok(hex.encode(chunk)));
+    };
+    let mut first = true;
+    encode_chunks(reader, writer, |chunk| {
+        let mut out = String::with_capacity(chunk.len() * (2 + sep.len()));
+        for byte in chunk {
+            if !first {
+                out.push_str(sep);
+            }
+            first = false;
+            out.push_str(&hex.encode(&[*byte]));
+        }
+        Ok(out)
+    })
+}
+
+// 分块解码：去掉空白和分隔符后凑满 `group` 个字符的整数倍再交给 `decode`，余下的字符留到下一块；
+// 分隔符可能被拆在两块之间，因此每次只处理到最后一个完整的分隔符为止
+fn decode_chunks(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    group: usize,
+    separator: Option<&str>,
+    decode: impl Fn(&[u8], bool) -> anyhow::Result<Vec<u8>>,
+) -> anyhow::Result<()> {
+    let separator = separator.filter(|sep| !sep.is_empty()).map(str::as_bytes);
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut raw = Vec::new();
+    let mut digits = Vec::new();
+    loop {
+        let n = reader.read(&mut buf)?;
+        let last = n == 0;
+        raw.extend_from_slice(&buf[..n]);
+        let cut = match separator {
+            Some(sep) if !last => raw
+                .windows(sep.len())
+                .rposition(|window| window == sep)
+                .map_or(0, |i| i + sep.len()),
+            _ => raw.len(),
+        };
+        let mut rest = &raw[..cut];
+        while let Some((&byte, tail)) = rest.split_first() {
+            match separator {
+                Some(sep) if rest.starts_with(sep) => rest = &rest[sep.len()..],
+                _ => {
+                    if !byte.is_ascii_whitespace() {
+                        digits.push(byte);
+                    }
+                    rest = tail;
+                }
+            }
+        }
+        raw.drain(..cut);
+
+        // 最后一组留到读完时再解码，带填充的分组因此总是作为最后一块处理
+        let ready = if last {
+            digits.len()
+        } else {
+            digits.len().saturating_sub(1) / group * group
+        };
+        writer.write_all(&decode(&digits[..ready], last)?)?;
+        digits.drain(..ready);
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+// 无法分块的编码：读入全部文本，去掉首尾空白后整体解码
+fn decode_whole(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    decode: impl Fn(&str) -> anyhow::Result<Vec<u8>>,
+) -> anyhow::Result<()> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    writer.write_all(&decode(text.trim())?)?;
+    Ok(())
+}
+
+fn strip_whitespace(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+// Crockford base32：不含 I/L/O/U，解码时忽略大小写和 `-`，并把 O 当作 0、I/L 当作 1
+fn crockford() -> anyhow::Result<DataEncoding> {
+    let mut spec = Specification::new();
+    spec.symbols.push_str("0123456789ABCDEFGHJKMNPQRSTVWXYZ");
+    spec.translate.from.push_str("abcdefghjkmnpqrstvwxyzOoIiLl");
+    spec.translate.to.push_str("ABCDEFGHJKMNPQRSTVWXYZ001111");
+    spec.ignore.push('-');
+    Ok(spec.encoding()?)
+}
+
+fn base85_digits(group: [u8; 4]) -> [u8; 5] {
+    let mut value = u32::from_be_bytes(group);
+    let mut digits = [0u8; 5];
+    for d in digits.iter_mut().rev() {
+        *d = (value % 85) as u8;
+        value /= 85;
+    }
+    digits
+}
+
+fn base85_value(digits: &[u8]) -> anyhow::Result<[u8; 4]> {
+    let value = digits
+        .iter()
+        .try_fold(0u64, |acc, d| Some(acc * 85 + *d as u64))
+        .filter(|v| *v <= u32::MAX as u64)
+        .ok_or_else(|| anyhow::anyhow!("base85 分组溢出"))?;
+    Ok((value as u32).to_be_bytes())
+}
+
+/// Adobe 风格的 Ascii85：全零分组写作 `z`，末尾不足 4 字节的分组只输出 n+1 个字符。
+fn ascii85_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 5 / 4 + 5);
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        if chunk.len() == 4 && group == [0; 4] {
+            out.push('z');
+            continue;
+        }
+        let digits = base85_digits(group);
+        for d in &digits[..chunk.len() + 1] {
+            out.push((d + b'!') as char);
+        }
+    }
+    out
+}
+
+fn ascii85_decode(text: &str) -> anyhow::Result<Vec<u8>> {
+    let text = text.strip_prefix("<~").unwrap_or(text);
+    let text = text.strip_suffix("~>").unwrap_or(text);
+    let mut out = Vec::with_capacity(text.len() * 4 / 5);
+    let mut group = Vec::with_capacity(5);
+    for c in text.bytes() {
+        match c {
+            b'z' if group.is_empty() => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group.push(c - b'!');
+                if group.len() == 5 {
+                    out.extend_from_slice(&base85_value(&group)?);
+                    group.clear();
+                }
+            }
+            _ => anyhow::bail!("非法的 Ascii85 字符: {:?}", c as char),
+        }
+    }
+    if !group.is_empty() {
+        if group.len() == 1 {
+            anyhow::bail!("Ascii85 末尾分组长度不合法");
+        }
+        let n = group.len() - 1;
+        // 用最大数字补齐，截断后即可还原原始字节
+        group.resize(5, 84);
+        out.extend_from_slice(&base85_value(&group)?[..n]);
+    }
+    Ok(out)
+}
+
+/// ZeroMQ Z85：输入长度必须是 4 的倍数。
+fn z85_encode(data: &[u8]) -> anyhow::Result<String> {
+    if !data.len().is_multiple_of(4) {
+        anyhow::bail!("Z85 编码的输入长度必须是 4 的倍数");
+    }
+    let mut out = String::with_capacity(data.len() * 5 / 4);
+    for chunk in data.chunks(4) {
+        let digits = base85_digits(chunk.try_into()?);
+        out.extend(digits.iter().map(|d| Z85_ALPHABET[*d as usize] as char));
+    }
+    Ok(out)
+}
+
+fn z85_decode(text: &str) -> anyhow::Result<Vec<u8>> {
+    if !text.len().is_multiple_of(5) {
+        anyhow::bail!("Z85 文本长度必须是 5 的倍数");
+    }
+    let mut out = Vec::with_capacity(text.len() * 4 / 5);
+    for chunk in text.as_bytes().chunks(5) {
+        let digits = chunk
+            .iter()
+            .map(|c| {
+                Z85_ALPHABET
+                    .iter()
+                    .position(|a| a == c)
+                    .map(|d| d as u8)
+                    .ok_or_else(|| anyhow::anyhow!("非法的 Z85 字符: {:?}", *c as char))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        out.extend_from_slice(&base85_value(&digits)?);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{cli::base64::Base64Format, utils::TestDir};
+
+    #[test]
+    fn test_known_vectors() -> anyhow::Result<()> {
+        let cases: [(&[u8], Encoding, Option<&str>, &str); 10] = [
+            (b"\xde\xad\xbe\xef", Encoding::Hex, None, "deadbeef"),
+            (
+                b"\xde\xad\xbe\xef",
+                Encoding::HexUpper,
+                Some(":"),
+                "DE:AD:BE:EF",
+            ),
+            (b"foobar", Encoding::Base32, None, "MZXW6YTBOI======"),
+            (b"foobar", Encoding::Base32Crockford, None, "CSQPYRK1E8"),
+            (b"Hello World!", Encoding::Base58, None, "2NEpo7TZRRrLZSi2U"),
+            (b"Man ", Encoding::Ascii85, None, "9jqo^"),
+            (b"\0\0\0\0ab", Encoding::Ascii85, None, "z@:B"),
+            (
+                b"\x86\x4F\xD2\x6F\xB5\x59\xF7\x5B",
+                Encoding::Z85,
+                None,
+                "HelloWorld",
+            ),
+            (b"a b/c~", Encoding::Percent, None, "a%20b%2Fc~"),
+            (
+                b"hi",
+                Encoding::Base64(Base64Format::Standard),
+                None,
+                "aGk=",
+            ),
+        ];
+        let dir = TestDir::new()?;
+        for (data, encoding, sep, expected) in cases {
+            let mut encoded = Vec::new();
+            let input = dir.file(&format!("{}.bin", encoding));
+            std::fs::write(&input, data)?;
+            process_encoding_encode(&input, &mut encoded, encoding, sep)?;
+            assert_eq!(String::from_utf8(encoded)?, expected);
+
+            std::fs::write(&input, expected)?;
+            let mut decoded = Vec::new();
+            process_encoding_decode(&input, &mut decoded, encoding, sep)?;
+            assert_eq!(decoded, data);
+        }
+        Ok(())
+    }
+
+    fn decode(text: &str, encoding: Encoding, sep: Option<&str>) -> anyhow::Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        decode_reader(&mut text.as_bytes(), &mut decoded, encoding, sep)?;
+        Ok(decoded)
+    }
+
+    #[test]
+    fn test_lenient_decoding() -> anyhow::Result<()> {
+        assert_eq!(
+            decode("DE AD be ef\n", Encoding::Hex, None)?,
+            b"\xde\xad\xbe\xef"
+        );
+        assert_eq!(decode("mzxw6ytboi", Encoding::Base32, None)?, b"foobar");
+        assert_eq!(
+            decode("csqp-yrk1-e8", Encoding::Base32Crockford, None)?,
+            b"foobar"
+        );
+        assert_eq!(decode("<~9jqo^~>", Encoding::Ascii85, None)?, b"Man ");
+        assert!(decode("abc", Encoding::Hex, None).is_err());
+        assert!(decode("0OIl", Encoding::Base58, None).is_err());
+        assert!(z85_encode(b"abc").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunked_encodings() -> anyhow::Result<()> {
+        let data = (0..CHUNK_SIZE * 2 + 7)
+            .map(|i| (i * 7) as u8)
+            .collect::<Vec<_>>();
+        let dir = TestDir::new()?;
+        let input = dir.file("data.bin");
+        std::fs::write(&input, &data)?;
+        for (encoding, sep) in [
+            (Encoding::Hex, Some(", ")),
+            (Encoding::HexUpper, None),
+            (Encoding::Base32, None),
+            (Encoding::Base32Crockford, None),
+            (Encoding::Ascii85, None),
+            (Encoding::Percent, None),
+        ] {
+            let mut encoded = Vec::new();
+            process_encoding_encode(&input, &mut encoded, encoding, sep)?;
+            // 整块编码的结果与分块编码一致
+            if let Encoding::Base32 = encoding {
+                assert_eq!(encoded, BASE32.encode(&data).as_bytes());
+            }
+            let mut decoded = Vec::new();
+            decode_reader(&mut &encoded[..], &mut decoded, encoding, sep)?;
+            assert_eq!(decoded, data);
+        }
+
+        // 分隔符和分组被拆在多次读取之间
+        let mut reader = "de,"
+            .as_bytes()
+            .chain(" ad, b".as_bytes())
+            .chain("e, ef\n".as_bytes());
+        let mut decoded = Vec::new();
+        decode_reader(&mut reader, &mut decoded, Encoding::Hex, Some(", "))?;
+        assert_eq!(decoded, b"\xde\xad\xbe\xef");
+        Ok(())
+    }
+
+    #[test]
+    fn test_ascii85_roundtrip_partial_groups() -> anyhow::Result<()> {
+        let data = (0u8..=255).collect::<Vec<_>>();
+        for len in 0..12 {
+            let encoded = ascii85_encode(&data[250 - len..]);
+            assert_eq!(ascii85_decode(&encoded)?, &data[250 - len..]);
+        }
+        Ok(())
+    }
+}
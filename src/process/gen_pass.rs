@@ -1,25 +1,45 @@
+use std::{fs, path::Path};
+
 use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::utils::CliRng;
 
 static LOWERCASE: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
 static UPPERCASE: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
 static NUMBER: &[u8] = b"123456789";
 static SYMBOL: &[u8] = b"!@#$%^&*()-_=+";
+// 适合口头念出的字母：去掉了容易听错的 c/l/q/w/x/y
+static CONSONANT: &[u8] = b"bdfghjkmnprstvz";
+static VOWEL: &[u8] = b"aeiou";
+
+/// 单个密码的最大长度，足够生成长共享密钥。
+pub const MAX_PASSWORD_LENGTH: usize = 65536;
 
+/// 生成随机密码。
+///
+/// 每个启用的字符类型先各抽取一个字符以保证覆盖，剩余位置从所有启用字符的并集中
+/// 均匀抽取（`choose` 基于拒绝采样，无取模偏差），最后整体洗牌。因此对长度为 `L`、
+/// 启用 `k` 个类型、并集大小为 `N` 的密码，类型 `i`（大小 `n_i`）中某个字符的期望出现
+/// 次数为 `1/n_i + (L-k)/N`：较小类型的字符会略多一些，`L` 越大这一偏差越可以忽略。
 pub fn process_gen_pass(
-    length: u8,
+    length: usize,
     lowercase: bool,
     uppercase: bool,
     number: bool,
     symbol: bool,
+    rng: &mut CliRng,
 ) -> anyhow::Result<String> {
-    let mut rng = rand::thread_rng();
-    let mut password = Vec::new();
+    if length > MAX_PASSWORD_LENGTH {
+        anyhow::bail!("密码长度不能超过 {}", MAX_PASSWORD_LENGTH);
+    }
+    let mut password = Vec::with_capacity(length);
     let mut chars = Vec::new();
 
     if lowercase {
         chars.extend_from_slice(LOWERCASE);
         let c = LOWERCASE
-            .choose(&mut rng)
+            .choose(rng)
             .expect("在这个上下文中，字符不会为空。");
         password.push(*c);
     }
@@ -27,37 +47,445 @@ pub fn process_gen_pass(
     if uppercase {
         chars.extend_from_slice(UPPERCASE);
         let c = UPPERCASE
-            .choose(&mut rng)
+            .choose(rng)
             .expect("在这个上下文中，字符不会为空。");
         password.push(*c);
     }
 
     if number {
         chars.extend_from_slice(NUMBER);
-        let c = NUMBER
-            .choose(&mut rng)
-            .expect("在这个上下文中，字符不会为空。");
+        let c = NUMBER.choose(rng).expect("在这个上下文中，字符不会为空。");
         password.push(*c);
     }
 
     if symbol {
         chars.extend_from_slice(SYMBOL);
-        let c = SYMBOL
-            .choose(&mut rng)
-            .expect("在这个上下文中，字符不会为空。");
+        let c = SYMBOL.choose(rng).expect("在这个上下文中，字符不会为空。");
         password.push(*c);
     }
 
-    for _ in 0..(length - password.len() as u8) {
-        let c = chars
-            .choose(&mut rng)
-            .expect("在这个上下文中，字符不会为空。");
+    if chars.is_empty() {
+        anyhow::bail!("至少需要启用一种字符类型");
+    }
+    let Some(remaining) = length.checked_sub(password.len()) else {
+        anyhow::bail!(
+            "密码长度至少为 {}，才能包含所有启用的字符类型",
+            password.len()
+        );
+    };
+
+    for _ in 0..remaining {
+        let c = chars.choose(rng).expect("在这个上下文中，字符不会为空。");
         password.push(*c)
     }
 
-    password.shuffle(&mut rng);
+    password.shuffle(rng);
 
     let password = String::from_utf8(password)?;
 
     Ok(password)
 }
+
+/// 按模板生成密码：`c`/`C` 小写/大写辅音，`v`/`V` 小写/大写元音，
+/// `9` 数字，`!` 符号，`\` 转义下一个字符，其余字符原样保留。
+pub fn process_gen_pattern(pattern: &str, rng: &mut CliRng) -> anyhow::Result<String> {
+    let mut password = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(p) = chars.next() {
+        let class = match p {
+            'c' | 'C' => CONSONANT,
+            'v' | 'V' => VOWEL,
+            '9' => NUMBER,
+            '!' => SYMBOL,
+            '\\' => {
+                match chars.next() {
+                    Some(c) => password.push(c),
+                    None => anyhow::bail!("模板不能以转义符结尾"),
+                }
+                continue;
+            }
+            c => {
+                password.push(c);
+                continue;
+            }
+        };
+        let c = *class.choose(rng).expect("在这个上下文中，字符不会为空。") as char;
+        if p.is_ascii_uppercase() {
+            password.push(c.to_ascii_uppercase());
+        } else {
+            password.push(c);
+        }
+    }
+    Ok(password)
+}
+
+/// 生成由“辅音+元音”音节交替组成的易读密码，便于电话中口述。
+pub fn process_gen_pronounceable(length: usize, rng: &mut CliRng) -> anyhow::Result<String> {
+    if length > MAX_PASSWORD_LENGTH {
+        anyhow::bail!("密码长度不能超过 {}", MAX_PASSWORD_LENGTH);
+    }
+    let pattern = "cv".repeat(length / 2 + 1);
+    let mut password = process_gen_pattern(&pattern, rng)?;
+    password.truncate(length);
+    Ok(password)
+}
+
+/// 密码策略文件（YAML/TOML），描述目标系统对密码的要求。
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub number: bool,
+    pub symbol: bool,
+    /// 不允许出现的子串（忽略大小写）
+    pub forbidden: Vec<String>,
+    /// 同一字符最多连续出现的次数
+    pub max_repeat: Option<usize>,
+    /// 最长允许的连续序列（如 `abcd`、`4321`）长度
+    pub max_sequence: Option<usize>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: MAX_PASSWORD_LENGTH,
+            lowercase: true,
+            uppercase: true,
+            number: true,
+            symbol: true,
+            forbidden: Vec::new(),
+            max_repeat: None,
+            max_sequence: None,
+        }
+    }
+}
+
+const POLICY_MAX_ATTEMPTS: usize = 1000;
+
+impl PasswordPolicy {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        let policy: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content)?,
+            _ => serde_yaml::from_str(&content)?,
+        };
+        policy.check()?;
+        Ok(policy)
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        let classes = [self.lowercase, self.uppercase, self.number, self.symbol]
+            .iter()
+            .filter(|enabled| **enabled)
+            .count();
+        if classes == 0 {
+            anyhow::bail!("策略至少需要启用一种字符类型");
+        }
+        if self.max_length > MAX_PASSWORD_LENGTH {
+            anyhow::bail!("策略的 max_length 不能超过 {}", MAX_PASSWORD_LENGTH);
+        }
+        if self.min_length > self.max_length {
+            anyhow::bail!("策略的 min_length 不能大于 max_length");
+        }
+        if self.max_length < classes {
+            anyhow::bail!("策略的 max_length 不足以包含所有必需的字符类型");
+        }
+        if self.max_repeat == Some(0) || self.max_sequence == Some(0) {
+            anyhow::bail!("策略的 max_repeat/max_sequence 必须大于 0");
+        }
+        Ok(())
+    }
+
+    /// 检查密码是否满足策略，不满足时返回具体原因。
+    pub fn validate(&self, password: &str) -> anyhow::Result<()> {
+        let bytes = password.as_bytes();
+        let length = bytes.len();
+        if length < self.min_length || length > self.max_length {
+            anyhow::bail!(
+                "密码长度 {} 不在 {}..={} 范围内",
+                length,
+                self.min_length,
+                self.max_length
+            );
+        }
+
+        let required = [
+            (self.lowercase, "小写字母", LOWERCASE),
+            (self.uppercase, "大写字母", UPPERCASE),
+            (self.number, "数字", NUMBER),
+            (self.symbol, "符号", SYMBOL),
+        ];
+        for (enabled, name, class) in required {
+            if enabled && !bytes.iter().any(|c| class.contains(c)) {
+                anyhow::bail!("密码缺少{}", name);
+            }
+        }
+        // 只允许出现已启用字符类型中的字符，例如 symbol 为 false 时不能有符号
+        if let Some(c) = password.chars().find(|c| {
+            !required
+                .iter()
+                .any(|(enabled, _, class)| *enabled && c.is_ascii() && class.contains(&(*c as u8)))
+        }) {
+            anyhow::bail!("密码包含策略不允许的字符 {:?}", c);
+        }
+
+        let lower = password.to_lowercase();
+        if let Some(word) = self
+            .forbidden
+            .iter()
+            .find(|word| !word.is_empty() && lower.contains(&word.to_lowercase()))
+        {
+            anyhow::bail!("密码包含禁止的子串 {:?}", word);
+        }
+
+        if let Some(max) = self.max_repeat {
+            if longest_run(bytes, |a, b| a == b) > max {
+                anyhow::bail!("同一字符连续出现超过 {} 次", max);
+            }
+        }
+
+        if let Some(max) = self.max_sequence {
+            let ascending = longest_run(bytes, |a, b| a.checked_add(1) == Some(b));
+            let descending = longest_run(bytes, |a, b| b.checked_add(1) == Some(a));
+            if ascending.max(descending) > max {
+                anyhow::bail!("密码包含长度超过 {} 的连续序列", max);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// 相邻字符满足 f 的最长连续段长度
+fn longest_run(bytes: &[u8], f: impl Fn(u8, u8) -> bool) -> usize {
+    let mut longest = bytes.len().min(1);
+    let mut current = longest;
+    for w in bytes.windows(2) {
+        if f(w[0], w[1]) {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 1;
+        }
+    }
+    longest
+}
+
+/// 按策略生成密码：长度限制在策略范围内，生成后重新校验，不满足则重试。
+pub fn process_gen_pass_with_policy(
+    length: usize,
+    policy: &PasswordPolicy,
+    rng: &mut CliRng,
+) -> anyhow::Result<String> {
+    policy.check()?;
+    let length = length.clamp(policy.min_length, policy.max_length);
+    for _ in 0..POLICY_MAX_ATTEMPTS {
+        let password = process_gen_pass(
+            length,
+            policy.lowercase,
+            policy.uppercase,
+            policy.number,
+            policy.symbol,
+            rng,
+        )?;
+        if policy.validate(&password).is_ok() {
+            return Ok(password);
+        }
+    }
+    anyhow::bail!("尝试 {} 次仍无法生成满足策略的密码", POLICY_MAX_ATTEMPTS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gen_pass_seeded_is_deterministic() -> anyhow::Result<()> {
+        let a = process_gen_pass(
+            16,
+            true,
+            true,
+            true,
+            true,
+            &mut CliRng::insecure_from_seed(42),
+        )?;
+        let b = process_gen_pass(
+            16,
+            true,
+            true,
+            true,
+            true,
+            &mut CliRng::insecure_from_seed(42),
+        )?;
+        assert_eq!(a, b);
+        assert_eq!(a, "_HN(@E7=wHf7eKn1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pattern() -> anyhow::Result<()> {
+        let mut rng = CliRng::insecure_from_seed(42);
+        let password = process_gen_pattern("Cvccvc-9999-!!\\c", &mut rng)?;
+        let bytes = password.as_bytes();
+        assert_eq!(bytes.len(), 15);
+        assert!(CONSONANT.contains(&bytes[0].to_ascii_lowercase()));
+        assert!(bytes[0].is_ascii_uppercase());
+        assert!(VOWEL.contains(&bytes[1]));
+        assert!(CONSONANT.contains(&bytes[2]));
+        assert_eq!(bytes[6], b'-');
+        assert!(bytes[7..11].iter().all(|c| NUMBER.contains(c)));
+        assert!(bytes[12..14].iter().all(|c| SYMBOL.contains(c)));
+        assert_eq!(&bytes[14..], b"c");
+        assert!(process_gen_pattern("9\\", &mut rng).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pronounceable() -> anyhow::Result<()> {
+        let mut rng = CliRng::insecure_from_seed(42);
+        for length in [0, 1, 7, 12] {
+            let password = process_gen_pronounceable(length, &mut rng)?;
+            assert_eq!(password.len(), length);
+            for (i, c) in password.bytes().enumerate() {
+                let class = if i % 2 == 0 { CONSONANT } else { VOWEL };
+                assert!(class.contains(&c));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_load() -> anyhow::Result<()> {
+        let policy = PasswordPolicy::load("fixtrues/policy.yaml")?;
+        assert_eq!(policy.min_length, 12);
+        assert!(!policy.symbol);
+        assert_eq!(policy.forbidden, vec!["admin".to_string()]);
+
+        let policy: PasswordPolicy = toml::from_str("max_length = 10\nmax_repeat = 2")?;
+        assert_eq!(policy.max_length, 10);
+        assert_eq!(policy.max_repeat, Some(2));
+        assert!(policy.symbol);
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_validate() {
+        let policy = PasswordPolicy {
+            min_length: 6,
+            max_length: 12,
+            symbol: false,
+            forbidden: vec!["Admin".to_string()],
+            max_repeat: Some(2),
+            max_sequence: Some(3),
+            ..Default::default()
+        };
+        assert!(policy.validate("aB3xY7").is_ok());
+        assert!(policy.validate("aB3xY").is_err());
+        assert!(policy.validate("ab3xy7").is_err());
+        assert!(policy.validate("xADMINy7").is_err());
+        assert!(policy.validate("aaaB3x").is_err());
+        assert!(policy.validate("abcdB3").is_err());
+        assert!(policy.validate("x9876B").is_err());
+        // 未启用的字符类型和字母表之外的字符都会被拒绝
+        assert!(policy.validate("aB3xY7!").is_err());
+        assert!(policy.validate("aB3xY7 ").is_err());
+        assert!(policy.validate("aB3xY7é").is_err());
+    }
+
+    #[test]
+    fn test_gen_pass_with_policy() -> anyhow::Result<()> {
+        let policy = PasswordPolicy::load("fixtrues/policy.yaml")?;
+        let mut rng = CliRng::insecure_from_seed(42);
+        for length in [4, 16, 64] {
+            let password = process_gen_pass_with_policy(length, &policy, &mut rng)?;
+            assert!(policy.validate(&password).is_ok());
+            assert_eq!(password.len(), length.clamp(12, 20));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pass_length() -> anyhow::Result<()> {
+        let mut rng = CliRng::insecure_from_seed(42);
+        let password = process_gen_pass(4096, true, true, true, true, &mut rng)?;
+        assert_eq!(password.len(), 4096);
+        assert_eq!(
+            process_gen_pass(4, true, true, true, true, &mut rng)?.len(),
+            4
+        );
+        assert!(process_gen_pass(3, true, true, true, true, &mut rng).is_err());
+        assert!(process_gen_pass(16, false, false, false, false, &mut rng).is_err());
+        assert!(
+            process_gen_pass(MAX_PASSWORD_LENGTH + 1, true, true, true, true, &mut rng).is_err()
+        );
+        Ok(())
+    }
+
+    // 卡方统计量：sum((观测 - 期望)^2 / 期望)
+    fn chi_square(password: &[u8], alphabet: &[u8], expected: impl Fn(u8) -> f64) -> f64 {
+        alphabet
+            .iter()
+            .map(|c| {
+                let observed = password.iter().filter(|p| *p == c).count() as f64;
+                let expected = expected(*c);
+                (observed - expected).powi(2) / expected
+            })
+            .sum()
+    }
+
+    // 自由度为 df 时 p = 0.001 的卡方临界值（Wilson–Hilferty 近似）
+    fn chi_square_critical(df: usize) -> f64 {
+        let df = df as f64;
+        let z = 3.09;
+        let t = 2.0 / (9.0 * df);
+        df * (1.0 - t + z * t.sqrt()).powi(3)
+    }
+
+    #[test]
+    fn test_gen_pass_single_class_is_uniform() -> anyhow::Result<()> {
+        let mut rng = CliRng::insecure_from_seed(7);
+        let classes = [
+            (LOWERCASE, [true, false, false, false]),
+            (UPPERCASE, [false, true, false, false]),
+            (NUMBER, [false, false, true, false]),
+            (SYMBOL, [false, false, false, true]),
+        ];
+        for (alphabet, [l, u, n, s]) in classes {
+            let mut samples = Vec::new();
+            for _ in 0..50 {
+                samples.extend(process_gen_pass(4096, l, u, n, s, &mut rng)?.into_bytes());
+            }
+            let expected = samples.len() as f64 / alphabet.len() as f64;
+            let stat = chi_square(&samples, alphabet, |_| expected);
+            assert!(stat < chi_square_critical(alphabet.len() - 1), "{stat}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pass_all_classes_matches_documented_distribution() -> anyhow::Result<()> {
+        let mut rng = CliRng::insecure_from_seed(7);
+        let (length, rounds) = (64, 5000);
+        let mut samples = Vec::new();
+        for _ in 0..rounds {
+            samples
+                .extend(process_gen_pass(length, true, true, true, true, &mut rng)?.into_bytes());
+        }
+        let classes = [LOWERCASE, UPPERCASE, NUMBER, SYMBOL];
+        let alphabet = classes.concat();
+        let expected = |c: u8| {
+            let class = classes.iter().find(|class| class.contains(&c)).unwrap();
+            let per_password =
+                1.0 / class.len() as f64 + (length - classes.len()) as f64 / alphabet.len() as f64;
+            per_password * rounds as f64
+        };
+        let stat = chi_square(&samples, &alphabet, expected);
+        assert!(stat < chi_square_critical(alphabet.len() - 1), "{stat}");
+        Ok(())
+    }
+}
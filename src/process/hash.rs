@@ -0,0 +1,231 @@
+use std::io::Read;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use data_encoding::HEXLOWER;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use sha3::Sha3_256;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::{
+    cli::hash::{DigestFormat, HashAlgorithm},
+    utils::{get_reader, update_from_reader},
+};
+
+/// 校验文件中单个条目的结果
+#[derive(Debug, PartialEq)]
+pub struct CheckResult {
+    pub path: String,
+    pub status: CheckStatus,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CheckStatus {
+    Ok,
+    /// 摘要不匹配
+    Mismatch,
+    /// 文件不存在或无法读取，附带 IO 错误信息
+    Unreadable(String),
+}
+
+/// 计算输入的摘要，`input` 为 `-` 时读取标准输入。
+pub fn process_hash(
+    input: &str,
+    algo: HashAlgorithm,
+    format: DigestFormat,
+) -> anyhow::Result<String> {
+    let mut reader = get_reader(input)?;
+    let digest = digest_reader(&mut reader, algo)?;
+    Ok(encode_digest(&digest, format))
+}
+
+/// 按 `sha256sum` 的格式（`<摘要>  <路径>`，二进制模式为 `<摘要> *<路径>`）逐行校验，
+/// `checksum_file` 为 `-` 时从标准输入读取校验和。
+pub fn process_hash_check(
+    checksum_file: &str,
+    algo: HashAlgorithm,
+    format: DigestFormat,
+) -> anyhow::Result<Vec<CheckResult>> {
+    let mut content = String::new();
+    get_reader(checksum_file)?.read_to_string(&mut content)?;
+    let mut results = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((expected, path)) = line.split_once(' ') else {
+            anyhow::bail!("第 {} 行格式不正确", n + 1);
+        };
+        let path = path
+            .strip_prefix(' ')
+            .or_else(|| path.strip_prefix('*'))
+            .unwrap_or(path);
+        let status = match process_hash(path, algo, format) {
+            Ok(actual) => {
+                let matched = match format {
+                    DigestFormat::Hex => actual.eq_ignore_ascii_case(expected),
+                    DigestFormat::Base64 => actual == expected,
+                };
+                if matched {
+                    CheckStatus::Ok
+                } else {
+                    CheckStatus::Mismatch
+                }
+            }
+            Err(e) => CheckStatus::Unreadable(e.to_string()),
+        };
+        results.push(CheckResult {
+            path: path.to_string(),
+            status,
+        });
+    }
+    Ok(results)
+}
+
+fn encode_digest(digest: &[u8], format: DigestFormat) -> String {
+    match format {
+        DigestFormat::Hex => HEXLOWER.encode(digest),
+        DigestFormat::Base64 => STANDARD.encode(digest),
+    }
+}
+
+fn digest_reader(reader: &mut dyn Read, algo: HashAlgorithm) -> anyhow::Result<Vec<u8>> {
+    let mut hasher = Hasher::new(algo);
+    update_from_reader(reader, |chunk| hasher.update(chunk))?;
+    Ok(hasher.finalize())
+}
+
+// 各算法的增量哈希状态
+enum Hasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Sha3_256(Sha3_256),
+    Blake3(Box<blake3::Hasher>),
+    Crc32(crc32fast::Hasher),
+    XxHash(Box<Xxh3>),
+}
+
+impl Hasher {
+    fn new(algo: HashAlgorithm) -> Self {
+        match algo {
+            HashAlgorithm::Md5 => Hasher::Md5(Md5::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            HashAlgorithm::Sha3_256 => Hasher::Sha3_256(Sha3_256::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::XxHash => Hasher::XxHash(Box::new(Xxh3::new())),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Sha1(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha512(h) => h.update(data),
+            Hasher::Sha3_256(h) => h.update(data),
+            Hasher::Blake3(h) => {
+                h.update(data);
+            }
+            Hasher::Crc32(h) => h.update(data),
+            Hasher::XxHash(h) => h.update(data),
+        }
+    }
+
+    // crc32/xxhash 按大端输出，与 `crc32`、`xxhsum` 等工具一致
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Md5(h) => h.finalize().to_vec(),
+            Hasher::Sha1(h) => h.finalize().to_vec(),
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+            Hasher::Sha512(h) => h.finalize().to_vec(),
+            Hasher::Sha3_256(h) => h.finalize().to_vec(),
+            Hasher::Blake3(h) => h.finalize().as_bytes().to_vec(),
+            Hasher::Crc32(h) => h.finalize().to_be_bytes().to_vec(),
+            Hasher::XxHash(h) => h.digest().to_be_bytes().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{fs, io::Cursor};
+
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_digest_known_vectors() -> anyhow::Result<()> {
+        let cases = [
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (HashAlgorithm::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha512,
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+            (
+                HashAlgorithm::Sha3_256,
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            ),
+            (
+                HashAlgorithm::Blake3,
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+            (HashAlgorithm::Crc32, "352441c2"),
+            (HashAlgorithm::XxHash, "78af5f94892f3950"),
+        ];
+        for (algo, expected) in cases {
+            let digest = digest_reader(&mut Cursor::new(b"abc"), algo)?;
+            assert_eq!(encode_digest(&digest, DigestFormat::Hex), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_streaming_matches_one_shot() -> anyhow::Result<()> {
+        let data = vec![7u8; 64 * 1024 * 2 + 3];
+        let digest = digest_reader(&mut Cursor::new(&data), HashAlgorithm::Sha256)?;
+        assert_eq!(digest, Sha256::digest(&data).to_vec());
+        assert_eq!(
+            encode_digest(&digest[..3], DigestFormat::Base64),
+            STANDARD.encode(&digest[..3])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_check() -> anyhow::Result<()> {
+        let sum = process_hash("Cargo.toml", HashAlgorithm::Sha256, DigestFormat::Hex)?;
+        let dir = TestDir::new()?;
+        let checksums = dir.file("check.sha256");
+        fs::write(
+            &checksums,
+            format!(
+                "{}  Cargo.toml\n{} *Cargo.toml\n{}  fixtrues/blake3\n{}  missing.txt\n",
+                sum,
+                sum.to_uppercase(),
+                sum,
+                sum
+            ),
+        )?;
+        let results = process_hash_check(&checksums, HashAlgorithm::Sha256, DigestFormat::Hex)?;
+        assert_eq!(results[0].status, CheckStatus::Ok);
+        assert_eq!(results[1].status, CheckStatus::Ok);
+        assert_eq!(results[2].status, CheckStatus::Mismatch);
+        assert_eq!(results[2].path, "fixtrues/blake3");
+        // 缺失的文件与摘要不匹配区分开
+        assert!(matches!(results[3].status, CheckStatus::Unreadable(_)));
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::text::TextSignFormat,
+    process::text::{check_algorithm, load_signer, verify_reader},
+};
+
+const MANIFEST_HEADER: &str = "rcli-manifest v1";
+
+/// 目录签名清单：每个文件的相对路径与 blake3 摘要，以及对它们的签名。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub algorithm: String,
+    pub key_id: String,
+    /// 相对路径（以 `/` 分隔）到 blake3 摘要（hex）的映射，按路径排序
+    pub files: BTreeMap<String, String>,
+    pub signature: String,
+}
+
+/// 清单与当前目录的差异
+#[derive(Debug, Default, PartialEq)]
+pub struct ManifestReport {
+    pub changed: Vec<String>,
+    pub missing: Vec<String>,
+    pub added: Vec<String>,
+}
+
+impl ManifestReport {
+    pub fn is_clean(&self) -> bool {
+        self.changed.is_empty() && self.missing.is_empty() && self.added.is_empty()
+    }
+}
+
+impl Manifest {
+    // 被签名的内容：头部、算法、密钥 ID，以及 `<摘要>  <路径>` 每行一个文件；
+    // 路径不允许包含换行符（见 `check_path`），因此每行的划分是唯一的
+    fn signed_payload(algorithm: &str, key_id: &str, files: &BTreeMap<String, String>) -> Vec<u8> {
+        let mut payload = format!("{}\n{}\n{}\n", MANIFEST_HEADER, algorithm, key_id);
+        for (path, digest) in files {
+            payload.push_str(&format!("{}  {}\n", digest, path));
+        }
+        payload.into_bytes()
+    }
+}
+
+/// 对 `dir` 下的所有文件签名，`exclude` 通常是清单文件自身。
+pub fn process_manifest_sign(
+    dir: &Path,
+    key: &str,
+    format: TextSignFormat,
+    exclude: Option<&Path>,
+) -> anyhow::Result<String> {
+    let files = digest_tree(dir, exclude)?;
+    let signer = load_signer(key, format)?;
+    let key_id = signer.key_id();
+    let algorithm = format.to_string();
+    let payload = Manifest::signed_payload(&algorithm, &key_id, &files);
+    let signed = signer.sign(&mut &payload[..])?;
+    let manifest = Manifest {
+        algorithm,
+        key_id,
+        files,
+        signature: URL_SAFE_NO_PAD.encode(signed),
+    };
+    Ok(serde_json::to_string_pretty(&manifest)?)
+}
+
+/// 先按 `format` 验证清单签名，再与 `dir` 的当前内容比较，返回发生变化的文件。
+pub fn process_manifest_verify(
+    dir: &Path,
+    key: &str,
+    manifest: &Path,
+    format: TextSignFormat,
+) -> anyhow::Result<ManifestReport> {
+    let content = fs::read_to_string(manifest)?;
+    let manifest_data: Manifest = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("清单文件 {} 格式错误: {}", manifest.display(), e))?;
+    check_algorithm(&manifest_data.algorithm, format)?;
+    for path in manifest_data.files.keys() {
+        check_path(path)?;
+    }
+    let payload = Manifest::signed_payload(
+        &manifest_data.algorithm,
+        &manifest_data.key_id,
+        &manifest_data.files,
+    );
+    let sig = URL_SAFE_NO_PAD.decode(manifest_data.signature.as_bytes())?;
+    if !verify_reader(
+        &mut &payload[..],
+        key,
+        &sig,
+        Some(&manifest_data.key_id),
+        format,
+    )? {
+        anyhow::bail!("清单签名无效，清单可能被篡改");
+    }
+
+    let current = digest_tree(dir, Some(manifest))?;
+    let mut report = ManifestReport::default();
+    for (path, digest) in &manifest_data.files {
+        match current.get(path) {
+            Some(actual) if actual == digest => {}
+            Some(_) => report.changed.push(path.clone()),
+            None => report.missing.push(path.clone()),
+        }
+    }
+    report.added = current
+        .keys()
+        .filter(|path| !manifest_data.files.contains_key(*path))
+        .cloned()
+        .collect();
+    Ok(report)
+}
+
+// 含有换行符的路径会让被签名的内容产生歧义
+fn check_path(path: &str) -> anyhow::Result<()> {
+    if path.contains(['\n', '\r']) {
+        anyhow::bail!("路径中包含换行符，无法写入清单: {:?}", path);
+    }
+    Ok(())
+}
+
+// 递归计算目录下每个文件的 blake3 摘要；不跟随指向目录的符号链接
+fn digest_tree(dir: &Path, exclude: Option<&Path>) -> anyhow::Result<BTreeMap<String, String>> {
+    let exclude = exclude.and_then(|path| fs::canonicalize(path).ok());
+    let mut files = BTreeMap::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        for entry in fs::read_dir(dir.join(&relative))? {
+            let entry = entry?;
+            let relative = relative.join(entry.file_name());
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(relative);
+                continue;
+            }
+            if !path.is_file() || exclude.as_deref() == fs::canonicalize(&path).ok().as_deref() {
+                continue;
+            }
+            let name = relative
+                .iter()
+                .map(|part| {
+                    part.to_str()
+                        .ok_or_else(|| anyhow::anyhow!("路径不是合法的 UTF-8: {}", path.display()))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .join("/");
+            check_path(&name)?;
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(fs::File::open(&path)?)?;
+            files.insert(name, hasher.finalize().to_hex().to_string());
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ed25519_dalek::SigningKey;
+
+    use crate::utils::{CliRng, TestDir};
+
+    #[test]
+    fn test_manifest_sign_verify() -> anyhow::Result<()> {
+        let tmp = TestDir::new()?;
+        let dir = tmp.path().join("tree");
+        fs::create_dir_all(dir.join("sub"))?;
+        fs::write(dir.join("a.txt"), "a")?;
+        fs::write(dir.join("sub/b.txt"), "b")?;
+        fs::write(dir.join("sub/c.txt"), "c")?;
+        let manifest = dir.join("MANIFEST.json");
+
+        let content = process_manifest_sign(
+            &dir,
+            "fixtrues/ed25519.sk",
+            TextSignFormat::Ed25519,
+            Some(&manifest),
+        )?;
+        fs::write(&manifest, &content)?;
+        let parsed: Manifest = serde_json::from_str(&content)?;
+        let paths: Vec<_> = parsed.files.keys().cloned().collect();
+        assert_eq!(paths, ["a.txt", "sub/b.txt", "sub/c.txt"]);
+
+        let report = process_manifest_verify(
+            &dir,
+            "fixtrues/ed25519.pk",
+            &manifest,
+            TextSignFormat::Ed25519,
+        )?;
+        assert!(report.is_clean());
+
+        fs::write(dir.join("a.txt"), "changed")?;
+        fs::remove_file(dir.join("sub/c.txt"))?;
+        fs::write(dir.join("new.txt"), "new")?;
+        let report = process_manifest_verify(
+            &dir,
+            "fixtrues/ed25519.pk",
+            &manifest,
+            TextSignFormat::Ed25519,
+        )?;
+        assert_eq!(
+            report,
+            ManifestReport {
+                changed: vec!["a.txt".to_string()],
+                missing: vec!["sub/c.txt".to_string()],
+                added: vec!["new.txt".to_string()],
+            }
+        );
+
+        // 篡改清单中的摘要会使签名失效
+        let tampered = content.replacen("\"a.txt\": \"", "\"a.txt\": \"0", 1);
+        fs::write(&manifest, tampered)?;
+        assert!(process_manifest_verify(
+            &dir,
+            "fixtrues/ed25519.pk",
+            &manifest,
+            TextSignFormat::Ed25519
+        )
+        .is_err());
+
+        // 使用其他密钥验证时报告密钥不一致
+        fs::write(&manifest, &content)?;
+        let other = tmp.file("other.pk");
+        let key = SigningKey::generate(&mut CliRng::insecure_from_seed(9));
+        fs::write(&other, key.verifying_key().as_bytes())?;
+        let err =
+            process_manifest_verify(&dir, &other, &manifest, TextSignFormat::Ed25519).unwrap_err();
+        assert!(err.to_string().contains("不一致"));
+
+        // 用公钥作为 blake3 共享密钥伪造的清单不会被接受
+        let forged = process_manifest_sign(
+            &dir,
+            "fixtrues/ed25519.pk",
+            TextSignFormat::Blake3,
+            Some(&manifest),
+        )?;
+        fs::write(&manifest, forged)?;
+        let err = process_manifest_verify(
+            &dir,
+            "fixtrues/ed25519.pk",
+            &manifest,
+            TextSignFormat::Ed25519,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("算法"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_rejects_newline_in_path() {
+        assert!(check_path("sub/a.txt").is_ok());
+        assert!(check_path("a.txt\n0000  b.txt").is_err());
+        assert!(check_path("a\r.txt").is_err());
+    }
+}
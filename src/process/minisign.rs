@@ -0,0 +1,418 @@
+//! minisign / signify 兼容的密钥与签名文件，格式见 <https://jedisct1.github.io/minisign/>。
+
+use std::io::Read;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use blake2::{digest::consts::U32, Blake2b, Blake2b512, Digest};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+
+use crate::utils::CliRng;
+
+const SIG_ALG: &[u8; 2] = b"Ed";
+// 对 BLAKE2b-512 预哈希后的消息签名，minisign 0.10 起默认使用
+const SIG_ALG_PREHASHED: &[u8; 2] = b"ED";
+const KDF_NONE: &[u8; 2] = &[0, 0];
+const KDF_SCRYPT: &[u8; 2] = b"Sc";
+const CHECKSUM_ALG: &[u8; 2] = b"B2";
+
+const KEY_ID_SIZE: usize = 8;
+const SALT_SIZE: usize = 32;
+// key_id || seed || 公钥 || checksum，加密时整体与 scrypt 的输出异或
+const KEYNUM_SK_SIZE: usize = KEY_ID_SIZE + 64 + 32;
+const PUBLIC_KEY_SIZE: usize = 2 + KEY_ID_SIZE + 32;
+const SECRET_KEY_SIZE: usize = 2 + 2 + 2 + SALT_SIZE + 8 + 8 + KEYNUM_SK_SIZE;
+const SIGNATURE_SIZE: usize = 2 + KEY_ID_SIZE + 64;
+// 与 minisign 相同，加密私钥时使用 libsodium 的 OPSLIMIT_SENSITIVE / MEMLIMIT_SENSITIVE
+const OPSLIMIT: u64 = 33554432;
+const MEMLIMIT: u64 = 1073741824;
+// 解密私钥时允许的 scrypt 参数上限，防止伪造的文件在校验口令之前耗尽内存或 CPU；
+// opslimit 不超过 OPSLIMIT_SENSITIVE，即 minisign 自己会写出的最大值
+const MAX_OPSLIMIT: u64 = OPSLIMIT;
+const MAX_MEMLIMIT: u64 = 1 << 31;
+
+const UNTRUSTED_COMMENT: &str = "untrusted comment: ";
+const TRUSTED_COMMENT: &str = "trusted comment: ";
+
+pub type KeyId = [u8; KEY_ID_SIZE];
+
+/// 判断文本是否为 minisign 格式（以 untrusted comment 开头）
+pub fn is_minisign(text: &str) -> bool {
+    text.starts_with(UNTRUSTED_COMMENT)
+}
+
+/// 非 minisign 来源的密钥没有记录 ID，由公钥派生一个固定的 ID
+pub fn derive_key_id(key: &VerifyingKey) -> KeyId {
+    let hash = Blake2b512::digest(key.as_bytes());
+    hash[..KEY_ID_SIZE].try_into().unwrap()
+}
+
+fn format_key_id(key_id: &KeyId) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+pub fn encode_public_key(key_id: &KeyId, key: &VerifyingKey) -> String {
+    let blob = [&SIG_ALG[..], key_id, key.as_bytes()].concat();
+    format!(
+        "{}minisign public key {}\n{}\n",
+        UNTRUSTED_COMMENT,
+        format_key_id(key_id),
+        STANDARD.encode(blob)
+    )
+}
+
+/// 输出不加密的私钥，与 `minisign -G -W` 相同。
+pub fn encode_secret_key(key_id: &KeyId, key: &SigningKey) -> String {
+    encode_secret_key_with(key_id, key, None).expect("不加密时编码私钥不会失败")
+}
+
+/// 与 minisign 一样用口令经 scrypt 派生的密钥流加密私钥，可由 `minisign -S` 直接使用。
+pub fn encrypt_secret_key(
+    key_id: &KeyId,
+    key: &SigningKey,
+    passphrase: &str,
+    rng: &mut CliRng,
+) -> anyhow::Result<String> {
+    let mut salt = [0u8; SALT_SIZE];
+    rng.fill_bytes(&mut salt);
+    encode_secret_key_with(key_id, key, Some((passphrase, &salt, OPSLIMIT, MEMLIMIT)))
+}
+
+// `kdf` 为口令、盐和 scrypt 的 opslimit/memlimit，为空时不加密
+fn encode_secret_key_with(
+    key_id: &KeyId,
+    key: &SigningKey,
+    kdf: Option<(&str, &[u8; SALT_SIZE], u64, u64)>,
+) -> anyhow::Result<String> {
+    let mut keynum = Vec::with_capacity(KEYNUM_SK_SIZE);
+    keynum.extend_from_slice(key_id);
+    keynum.extend_from_slice(&key.to_keypair_bytes());
+    keynum.extend_from_slice(&checksum(key_id, &key.to_keypair_bytes()));
+
+    let mut blob = Vec::with_capacity(SECRET_KEY_SIZE);
+    blob.extend_from_slice(SIG_ALG);
+    match kdf {
+        None => {
+            blob.extend_from_slice(KDF_NONE);
+            blob.extend_from_slice(CHECKSUM_ALG);
+            blob.extend_from_slice(&[0u8; SALT_SIZE + 16]);
+        }
+        Some((passphrase, salt, opslimit, memlimit)) => {
+            let mut stream = [0u8; KEYNUM_SK_SIZE];
+            let params = scrypt_params(opslimit, memlimit)?;
+            scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut stream)
+                .map_err(|e| anyhow::anyhow!("派生密钥失败: {}", e))?;
+            keynum.iter_mut().zip(stream).for_each(|(b, s)| *b ^= s);
+            blob.extend_from_slice(KDF_SCRYPT);
+            blob.extend_from_slice(CHECKSUM_ALG);
+            blob.extend_from_slice(salt);
+            blob.extend_from_slice(&opslimit.to_le_bytes());
+            blob.extend_from_slice(&memlimit.to_le_bytes());
+        }
+    }
+    blob.extend_from_slice(&keynum);
+    Ok(format!(
+        "{}minisign secret key {}\n{}\n",
+        UNTRUSTED_COMMENT,
+        format_key_id(key_id),
+        STANDARD.encode(blob)
+    ))
+}
+
+fn checksum(key_id: &KeyId, keypair: &[u8]) -> [u8; 32] {
+    Blake2b::<U32>::new()
+        .chain_update(SIG_ALG)
+        .chain_update(key_id)
+        .chain_update(keypair)
+        .finalize()
+        .into()
+}
+
+// 取出第一行非注释内容并 base64 解码
+fn decode_blob(text: &str) -> anyhow::Result<Vec<u8>> {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_COMMENT))
+        .ok_or_else(|| anyhow::anyhow!("minisign 文件中没有密钥"))?;
+    STANDARD
+        .decode(line)
+        .map_err(|_| anyhow::anyhow!("minisign 密钥不是合法的 base64"))
+}
+
+/// 私钥与公钥文件长度不同，据此区分。
+pub fn is_secret_key(text: &str) -> anyhow::Result<bool> {
+    match decode_blob(text)?.len() {
+        PUBLIC_KEY_SIZE => Ok(false),
+        SECRET_KEY_SIZE => Ok(true),
+        len => anyhow::bail!("minisign 密钥长度错误：{} 字节", len),
+    }
+}
+
+/// 解析公钥文件，也接受 `minisign -P` 使用的单行 base64。
+pub fn decode_public_key(text: &str) -> anyhow::Result<(KeyId, VerifyingKey)> {
+    let blob = decode_blob(text)?;
+    if blob.len() != PUBLIC_KEY_SIZE || &blob[..2] != SIG_ALG {
+        anyhow::bail!("不是 minisign ed25519 公钥");
+    }
+    let key_id = blob[2..2 + KEY_ID_SIZE].try_into()?;
+    let key = VerifyingKey::from_bytes(blob[2 + KEY_ID_SIZE..].try_into()?)?;
+    Ok((key_id, key))
+}
+
+/// 解析私钥文件；私钥用 scrypt 加密时调用 `passphrase` 获取口令。
+pub fn decode_secret_key(
+    text: &str,
+    passphrase: impl FnOnce() -> anyhow::Result<String>,
+) -> anyhow::Result<(KeyId, SigningKey)> {
+    let blob = decode_blob(text)?;
+    if blob.len() != SECRET_KEY_SIZE || &blob[..2] != SIG_ALG || &blob[4..6] != CHECKSUM_ALG {
+        anyhow::bail!("不是 minisign ed25519 私钥");
+    }
+    let (salt, rest) = blob[6..].split_at(SALT_SIZE);
+    let opslimit = u64::from_le_bytes(rest[..8].try_into()?);
+    let memlimit = u64::from_le_bytes(rest[8..16].try_into()?);
+    let mut keynum = rest[16..].to_vec();
+    match blob[2..4].try_into()? {
+        KDF_NONE => {}
+        KDF_SCRYPT => {
+            let mut stream = [0u8; KEYNUM_SK_SIZE];
+            let params = scrypt_params(opslimit, memlimit)?;
+            scrypt::scrypt(passphrase()?.as_bytes(), salt, &params, &mut stream)
+                .map_err(|e| anyhow::anyhow!("派生密钥失败: {}", e))?;
+            keynum.iter_mut().zip(stream).for_each(|(b, s)| *b ^= s);
+        }
+        _ => anyhow::bail!("不支持的 minisign 私钥加密方式"),
+    }
+
+    let (key_id, rest) = keynum.split_at(KEY_ID_SIZE);
+    let (keypair, expected) = rest.split_at(64);
+    let key_id: KeyId = key_id.try_into()?;
+    if checksum(&key_id, keypair) != expected {
+        anyhow::bail!("解开 minisign 私钥失败：口令错误或文件已损坏");
+    }
+    let key = SigningKey::from_keypair_bytes(keypair.try_into()?)?;
+    Ok((key_id, key))
+}
+
+// 与 libsodium 的 crypto_pwhash_scryptsalsa208sha256 一样由 opslimit/memlimit 推出 N、r、p
+fn scrypt_params(opslimit: u64, memlimit: u64) -> anyhow::Result<scrypt::Params> {
+    if memlimit > MAX_MEMLIMIT {
+        anyhow::bail!("minisign 私钥的 scrypt 内存参数过大：{}", memlimit);
+    }
+    if opslimit > MAX_OPSLIMIT {
+        anyhow::bail!("minisign 私钥的 scrypt 计算量参数过大：{}", opslimit);
+    }
+    let opslimit = opslimit.max(32768);
+    let r: u64 = 8;
+    let (log_n, p) = if opslimit < memlimit / 32 {
+        let max_n = opslimit / (r * 4);
+        (pick_log_n(max_n), 1)
+    } else {
+        let max_n = memlimit / (r * 128);
+        let log_n = pick_log_n(max_n);
+        let max_rp = ((opslimit / 4) >> log_n).min(0x3fff_ffff);
+        (log_n, (max_rp / r).max(1))
+    };
+    // 输出长度只用于 PHC 字符串，实际长度由输出缓冲区决定
+    scrypt::Params::new(log_n, r as u32, p as u32, scrypt::Params::RECOMMENDED_LEN)
+        .map_err(|e| anyhow::anyhow!("minisign 私钥的 scrypt 参数不合法: {}", e))
+}
+
+fn pick_log_n(max_n: u64) -> u8 {
+    (1..63)
+        .find(|&log_n| 1u64 << log_n > max_n / 2)
+        .unwrap_or(63)
+}
+
+/// 生成 `.minisig` 签名文件，`prehashed` 为真时先对消息做 BLAKE2b-512。
+pub fn sign(
+    key_id: &KeyId,
+    key: &SigningKey,
+    reader: &mut dyn Read,
+    prehashed: bool,
+    trusted_comment: &str,
+) -> anyhow::Result<String> {
+    if trusted_comment.contains(['\r', '\n']) {
+        anyhow::bail!("trusted comment 不能包含换行");
+    }
+    let (alg, message) = if prehashed {
+        (SIG_ALG_PREHASHED, prehash(reader)?)
+    } else {
+        let mut message = Vec::new();
+        reader.read_to_end(&mut message)?;
+        (SIG_ALG, message)
+    };
+    let signature = key.sign(&message).to_bytes();
+    let global = key.sign(&[&signature[..], trusted_comment.as_bytes()].concat());
+    let blob = [&alg[..], key_id, &signature].concat();
+    Ok(format!(
+        "{}signature from rcli secret key\n{}\n{}{}\n{}\n",
+        UNTRUSTED_COMMENT,
+        STANDARD.encode(blob),
+        TRUSTED_COMMENT,
+        trusted_comment,
+        STANDARD.encode(global.to_bytes())
+    ))
+}
+
+fn prehash(reader: &mut dyn Read) -> anyhow::Result<Vec<u8>> {
+    let mut hasher = Blake2b512::new();
+    std::io::copy(reader, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+/// 验证 `.minisig` 签名文件，消息和 trusted comment 都通过时返回 trusted comment。
+pub fn verify(
+    key_id: &KeyId,
+    key: &VerifyingKey,
+    reader: &mut dyn Read,
+    minisig: &str,
+) -> anyhow::Result<Option<String>> {
+    let mut lines = minisig.lines();
+    // trusted comment 末尾的空格也参与签名，只去掉 CRLF 中的 `\r`
+    let mut next = || lines.next().map(|line| line.trim_end_matches('\r'));
+    let (Some(untrusted), Some(sig), Some(trusted), Some(global)) =
+        (next(), next(), next(), next())
+    else {
+        anyhow::bail!("minisign 签名文件不完整");
+    };
+    let trusted_comment = trusted
+        .strip_prefix(TRUSTED_COMMENT)
+        .filter(|_| untrusted.starts_with(UNTRUSTED_COMMENT))
+        .ok_or_else(|| anyhow::anyhow!("minisign 签名文件格式错误"))?;
+    let blob = STANDARD.decode(sig)?;
+    if blob.len() != SIGNATURE_SIZE {
+        anyhow::bail!("minisign 签名长度错误：{} 字节", blob.len());
+    }
+    let (alg, rest) = blob.split_at(2);
+    let (sig_key_id, signature) = rest.split_at(KEY_ID_SIZE);
+    if sig_key_id != key_id {
+        anyhow::bail!(
+            "签名使用的密钥 {} 与提供的密钥 {} 不一致",
+            format_key_id(sig_key_id.try_into()?),
+            format_key_id(key_id)
+        );
+    }
+    let message = match alg {
+        alg if alg == SIG_ALG_PREHASHED => prehash(reader)?,
+        alg if alg == SIG_ALG => {
+            let mut message = Vec::new();
+            reader.read_to_end(&mut message)?;
+            message
+        }
+        _ => anyhow::bail!("不支持的 minisign 签名算法"),
+    };
+    let global = Signature::from_slice(&STANDARD.decode(global)?)?;
+    let comment = [signature, trusted_comment.as_bytes()].concat();
+    if key.verify(&comment, &global).is_err() {
+        return Ok(None);
+    }
+    let signature = Signature::from_slice(signature)?;
+    Ok(key
+        .verify(&message, &signature)
+        .is_ok()
+        .then(|| trusted_comment.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // minisign-verify 自带测试中的公钥与签名
+    const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+    const MINISIG: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==
+";
+
+    #[test]
+    fn test_minisign_verify_stock_signature() -> anyhow::Result<()> {
+        let (key_id, key) = decode_public_key(PUBLIC_KEY)?;
+        let comment = verify(&key_id, &key, &mut &b"test"[..], MINISIG)?;
+        assert_eq!(comment.as_deref(), Some("timestamp:1555779966\tfile:test"));
+        assert_eq!(verify(&key_id, &key, &mut &b"tesT"[..], MINISIG)?, None);
+
+        // 篡改 trusted comment 会使全局签名失效
+        let tampered = MINISIG.replace("file:test", "file:evil");
+        assert_eq!(verify(&key_id, &key, &mut &b"test"[..], &tampered)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_minisign_sign_round_trip() -> anyhow::Result<()> {
+        let key = SigningKey::generate(&mut CliRng::insecure_from_seed(3));
+        let key_id = derive_key_id(&key.verifying_key());
+        let secret = encode_secret_key(&key_id, &key);
+        let public = encode_public_key(&key_id, &key.verifying_key());
+        assert!(is_secret_key(&secret)? && !is_secret_key(&public)?);
+
+        let (id, decoded) = decode_secret_key(&secret, || unreachable!())?;
+        assert_eq!((id, decoded.to_bytes()), (key_id, key.to_bytes()));
+        let (id, decoded) = decode_public_key(&public)?;
+        assert_eq!((id, decoded), (key_id, key.verifying_key()));
+
+        for prehashed in [true, false] {
+            let minisig = sign(&key_id, &key, &mut &b"hello"[..], prehashed, "file:hello")?;
+            let comment = verify(&key_id, &key.verifying_key(), &mut &b"hello"[..], &minisig)?;
+            assert_eq!(comment.as_deref(), Some("file:hello"));
+
+            // 用独立实现交叉验证
+            let pk = minisign_verify::PublicKey::from_base64(public.lines().nth(1).unwrap())?;
+            let signature = minisign_verify::Signature::decode(&minisig)?;
+            pk.verify(b"hello", &signature, !prehashed)?;
+        }
+
+        let other = [0u8; KEY_ID_SIZE];
+        let minisig = sign(&key_id, &key, &mut &b"hello"[..], true, "")?;
+        let err = verify(&other, &key.verifying_key(), &mut &b"hello"[..], &minisig).unwrap_err();
+        assert!(err.to_string().contains("不一致"));
+        assert!(sign(&key_id, &key, &mut &b""[..], true, "a\nb").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_minisign_encrypted_secret_key() -> anyhow::Result<()> {
+        let key = SigningKey::generate(&mut CliRng::insecure_from_seed(4));
+        let key_id = derive_key_id(&key.verifying_key());
+        let secret = encode_secret_key(&key_id, &key);
+
+        // 按 minisign 的方式用 scrypt 加密，参数取较小值以加快测试
+        let mut blob = decode_blob(&secret)?;
+        blob[2..4].copy_from_slice(KDF_SCRYPT);
+        blob[6..6 + SALT_SIZE].copy_from_slice(&[7u8; SALT_SIZE]);
+        blob[38..46].copy_from_slice(&32768u64.to_le_bytes());
+        blob[46..54].copy_from_slice(&(1u64 << 20).to_le_bytes());
+        let mut stream = [0u8; KEYNUM_SK_SIZE];
+        let params = scrypt_params(32768, 1 << 20)?;
+        scrypt::scrypt(b"pw", &[7u8; SALT_SIZE], &params, &mut stream).unwrap();
+        blob[54..].iter_mut().zip(stream).for_each(|(b, s)| *b ^= s);
+        let encrypted = format!("{}x\n{}\n", UNTRUSTED_COMMENT, STANDARD.encode(blob));
+
+        let (id, decoded) = decode_secret_key(&encrypted, || Ok("pw".to_string()))?;
+        assert_eq!((id, decoded.to_bytes()), (key_id, key.to_bytes()));
+        let err = decode_secret_key(&encrypted, || Ok("wrong".to_string())).unwrap_err();
+        assert!(err.to_string().contains("口令错误"));
+
+        // 自己加密的私钥与上面手工构造的结果一致
+        let encoded = encode_secret_key_with(
+            &key_id,
+            &key,
+            Some(("pw", &[7u8; SALT_SIZE], 32768, 1 << 20)),
+        )?;
+        assert_eq!(decode_blob(&encoded)?, decode_blob(&encrypted)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scrypt_params_match_libsodium() -> anyhow::Result<()> {
+        // minisign 默认的 OPSLIMIT_SENSITIVE / MEMLIMIT_SENSITIVE
+        let params = scrypt_params(33554432, 1073741824)?;
+        assert_eq!((params.log_n(), params.r(), params.p()), (20, 8, 1));
+        // 伪造的私钥文件不能要求超过 OPSLIMIT_SENSITIVE 的计算量
+        assert!(scrypt_params(OPSLIMIT + 1, MEMLIMIT).is_err());
+        assert!(scrypt_params(u64::MAX, MEMLIMIT).is_err());
+        Ok(())
+    }
+}
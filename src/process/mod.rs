@@ -1,6 +1,12 @@
 pub mod base64;
 pub mod csv;
+pub mod encoding;
 pub mod gen_pass;
+pub mod hash;
 pub mod http;
 pub mod jwt;
+pub mod key_format;
+pub mod manifest;
+pub mod minisign;
+pub mod otp;
 pub mod text;
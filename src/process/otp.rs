@@ -0,0 +1,270 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use qrcode::{render::unicode::Dense1x2, QrCode};
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::{cli::otp::OtpAlgorithm, utils::CliRng};
+
+/// 一次性密码的公共参数，对应 otpauth URI 中的同名字段。
+#[derive(Debug, Clone, Copy)]
+pub struct OtpParams {
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    /// 为 `Some` 时使用 HOTP（RFC 4226），否则使用 TOTP（RFC 6238）
+    pub counter: Option<u64>,
+}
+
+/// 生成 `len` 字节的随机密钥，返回不带填充的 base32 字符串。
+pub fn process_otp_secret(len: usize, rng: &mut CliRng) -> String {
+    let mut secret = vec![0u8; len];
+    rng.fill_bytes(&mut secret);
+    BASE32_NOPAD.encode(&secret)
+}
+
+pub fn process_otp_uri(
+    secret: &str,
+    account: &str,
+    issuer: Option<&str>,
+    params: OtpParams,
+) -> anyhow::Result<String> {
+    // 先解码一次，确保写进 URI 的密钥是合法的
+    decode_secret(secret)?;
+    if !(6..=8).contains(&params.digits) {
+        anyhow::bail!("验证码位数必须在 6 到 8 之间");
+    }
+    if params.counter.is_none() && params.period == 0 {
+        anyhow::bail!("时间步长必须大于 0");
+    }
+    let secret = normalize_secret(secret);
+    let encode = |s| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string();
+
+    let (kind, moving_factor) = match params.counter {
+        Some(counter) => ("hotp", format!("counter={}", counter)),
+        None => ("totp", format!("period={}", params.period)),
+    };
+    let label = match issuer {
+        Some(issuer) => format!("{}:{}", encode(issuer), encode(account)),
+        None => encode(account),
+    };
+    let mut uri = format!(
+        "otpauth://{}/{}?secret={}&algorithm={}&digits={}&{}",
+        kind,
+        label,
+        secret,
+        params.algorithm.to_string().to_uppercase(),
+        params.digits,
+        moving_factor
+    );
+    if let Some(issuer) = issuer {
+        uri.push_str("&issuer=");
+        uri.push_str(&encode(issuer));
+    }
+    Ok(uri)
+}
+
+/// 把 URI 渲染成可以在终端里扫描的二维码。
+pub fn process_otp_qr(uri: &str) -> anyhow::Result<String> {
+    let code = QrCode::new(uri.as_bytes())?;
+    let qr = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build();
+    Ok(qr)
+}
+
+pub fn process_otp_code(secret: &str, params: OtpParams) -> anyhow::Result<String> {
+    let secret = decode_secret(secret)?;
+    let counter = match params.counter {
+        Some(counter) => counter,
+        None => time_step(now()?, params.period)?,
+    };
+    hotp(&secret, counter, params)
+}
+
+/// 校验时允许的最大偏差，过大的窗口会显著降低暴力猜测的难度
+pub const MAX_SKEW: u64 = 10;
+
+/// 校验验证码，返回匹配到的计数器；TOTP 在当前时间步前后 `skew` 步内查找，
+/// HOTP 从给定计数器向后查找 `skew` 步。
+pub fn process_otp_verify(
+    secret: &str,
+    code: &str,
+    skew: u64,
+    params: OtpParams,
+) -> anyhow::Result<Option<u64>> {
+    if skew > MAX_SKEW {
+        anyhow::bail!("允许的偏差不能超过 {}", MAX_SKEW);
+    }
+    let secret = decode_secret(secret)?;
+    let (start, end) = match params.counter {
+        Some(counter) => (counter, counter.saturating_add(skew)),
+        None => {
+            let step = time_step(now()?, params.period)?;
+            (step.saturating_sub(skew), step.saturating_add(skew))
+        }
+    };
+    verify_range(&secret, code, start..=end, params)
+}
+
+fn verify_range(
+    secret: &[u8],
+    code: &str,
+    counters: impl Iterator<Item = u64>,
+    params: OtpParams,
+) -> anyhow::Result<Option<u64>> {
+    for counter in counters {
+        let expected = hotp(secret, counter, params)?;
+        if bool::from(expected.as_bytes().ct_eq(code.trim().as_bytes())) {
+            return Ok(Some(counter));
+        }
+    }
+    Ok(None)
+}
+
+fn hotp(secret: &[u8], counter: u64, params: OtpParams) -> anyhow::Result<String> {
+    if !(6..=8).contains(&params.digits) {
+        anyhow::bail!("验证码位数必须在 6 到 8 之间");
+    }
+    let msg = counter.to_be_bytes();
+    let hash = match params.algorithm {
+        OtpAlgorithm::Sha1 => hmac::<Hmac<Sha1>>(secret, &msg)?,
+        OtpAlgorithm::Sha256 => hmac::<Hmac<Sha256>>(secret, &msg)?,
+        OtpAlgorithm::Sha512 => hmac::<Hmac<Sha512>>(secret, &msg)?,
+    };
+    // RFC 4226 5.3 动态截断
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes(hash[offset..offset + 4].try_into()?) & 0x7fff_ffff;
+    let code = binary % 10u32.pow(params.digits);
+    Ok(format!("{:0width$}", code, width = params.digits as usize))
+}
+
+fn hmac<M: Mac + hmac::digest::KeyInit>(key: &[u8], msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut mac = <M as Mac>::new_from_slice(key)?;
+    mac.update(msg);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn time_step(timestamp: u64, period: u64) -> anyhow::Result<u64> {
+    if period == 0 {
+        anyhow::bail!("时间步长必须大于 0");
+    }
+    Ok(timestamp / period)
+}
+
+fn now() -> anyhow::Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+fn normalize_secret(secret: &str) -> String {
+    secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn decode_secret(secret: &str) -> anyhow::Result<Vec<u8>> {
+    let secret = BASE32_NOPAD
+        .decode(normalize_secret(secret).as_bytes())
+        .map_err(|e| anyhow::anyhow!("密钥不是合法的 base32: {}", e))?;
+    if secret.is_empty() {
+        anyhow::bail!("密钥不能为空");
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(algorithm: OtpAlgorithm, digits: u32) -> OtpParams {
+        OtpParams {
+            algorithm,
+            digits,
+            period: 30,
+            counter: None,
+        }
+    }
+
+    #[test]
+    fn test_hotp_rfc4226() -> anyhow::Result<()> {
+        let secret = b"12345678901234567890";
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            let otp = hotp(secret, counter as u64, params(OtpAlgorithm::Sha1, 6))?;
+            assert_eq!(&otp, code);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_totp_rfc6238() -> anyhow::Result<()> {
+        let sha1 = b"12345678901234567890".as_slice();
+        let sha256 = b"12345678901234567890123456789012".as_slice();
+        let sha512 = b"1234567890123456789012345678901234567890123456789012345678901234".as_slice();
+        let cases = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (2000000000, "69279037", "90698825", "38618901"),
+        ];
+        for (time, c1, c256, c512) in cases {
+            let step = time_step(time, 30)?;
+            assert_eq!(hotp(sha1, step, params(OtpAlgorithm::Sha1, 8))?, c1);
+            assert_eq!(hotp(sha256, step, params(OtpAlgorithm::Sha256, 8))?, c256);
+            assert_eq!(hotp(sha512, step, params(OtpAlgorithm::Sha512, 8))?, c512);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_otp_verify_skew() -> anyhow::Result<()> {
+        let secret = b"12345678901234567890";
+        let params = params(OtpAlgorithm::Sha1, 6);
+        assert_eq!(verify_range(secret, "969429", 2..=4, params)?, Some(3));
+        assert_eq!(verify_range(secret, "969429", 4..=6, params)?, None);
+
+        let secret = BASE32_NOPAD.encode(secret);
+        let code = process_otp_code(&secret, params)?;
+        assert!(process_otp_verify(&secret, &code, 1, params)?.is_some());
+        assert!(process_otp_verify(&secret, &code, u64::MAX, params).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_otp_secret_and_uri() -> anyhow::Result<()> {
+        let secret = process_otp_secret(20, &mut CliRng::insecure_from_seed(42));
+        assert_eq!(decode_secret(&secret)?.len(), 20);
+
+        let params = params(OtpAlgorithm::Sha256, 8);
+        let uri = process_otp_uri("gezd gnbv", "alice@example.com", Some("ACME Co"), params)?;
+        assert_eq!(
+            uri,
+            "otpauth://totp/ACME%20Co:alice%40example%2Ecom?secret=GEZDGNBV&algorithm=SHA256&digits=8&period=30&issuer=ACME%20Co"
+        );
+        assert!(process_otp_uri("not base32!", "alice", None, params).is_err());
+        let zero_period = OtpParams {
+            period: 0,
+            ..params
+        };
+        assert!(process_otp_uri("gezd gnbv", "alice", None, zero_period).is_err());
+        let short = OtpParams {
+            digits: 0,
+            ..params
+        };
+        assert!(process_otp_uri("gezd gnbv", "alice", None, short).is_err());
+        assert!(process_otp_qr(&uri).is_ok());
+        Ok(())
+    }
+}
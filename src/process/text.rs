@@ -1,20 +1,55 @@
-use crate::{cli::text::TextSignFormat, process::gen_pass::process_gen_pass, utils::get_reader};
-use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use crate::{
+    cli::{
+        base64::Base64Format,
+        text::{AeadCipher, KeyFormat, TextKeyKind, TextSignFormat},
+    },
+    process::{
+        base64::decode_reader,
+        key_format::{decode_signing_key_with_id, decode_verifying_key_with_id, encode_ed25519},
+        minisign,
+    },
+    utils::{get_reader, prompt_passphrase, update_from_reader, CliRng},
+};
+use aes_gcm::Aes256Gcm;
+use aes_gcm_siv::Aes256GcmSiv;
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    x25519,
+};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    write::EncoderWriter,
+    Engine as _,
+};
+use bech32::{ToBase32, Variant};
 use chacha20poly1305::{
-    aead::{Aead, AeadCore, KeyInit, OsRng as ChaCha20Poly1305AeadOsRng},
-    ChaCha20Poly1305, Nonce,
+    aead::{
+        generic_array::{typenum::Unsigned, GenericArray},
+        Aead, AeadCore, KeyInit, Payload,
+    },
+    ChaCha20Poly1305, Nonce, XChaCha20Poly1305,
 };
+use data_encoding::HEXLOWER_PERMISSIVE;
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
-use rand::rngs::OsRng;
-use std::{fs, io::Read, path::Path};
+use hmac::{
+    digest::{core_api::BlockSizeUser, Digest},
+    Mac, SimpleHmac,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+use std::{
+    fs,
+    io::{BufReader, Read, Write},
+    marker::PhantomData,
+    path::Path,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 pub fn process_sign(input: &str, key: &str, format: TextSignFormat) -> anyhow::Result<String> {
     let mut reader = get_reader(input)?;
-    let signed = match format {
-        TextSignFormat::Blake3 => Blake3::load(key)?.sign(&mut reader)?,
-        TextSignFormat::Ed25519 => Ed25519Signer::load(key)?.sign(&mut reader)?,
-        TextSignFormat::ChaCha20Poly1305 => anyhow::bail!("Unsupported format"),
-    };
+    let signed = load_signer(key, format)?.sign(&mut reader)?;
     let signed = URL_SAFE_NO_PAD.encode(&signed);
     Ok(signed)
 }
@@ -27,32 +62,340 @@ pub fn process_verify(
 ) -> anyhow::Result<bool> {
     let mut reader = get_reader(input)?;
     let sig = URL_SAFE_NO_PAD.decode(sig.as_bytes())?;
-    let verified = match format {
-        TextSignFormat::Blake3 => Blake3::load(key)?.verify(&mut reader, &sig)?,
-        TextSignFormat::Ed25519 => Ed25519Verifier::load(key)?.verify(&mut reader, &sig)?,
-        TextSignFormat::ChaCha20Poly1305 => anyhow::bail!("Unsupported format"),
+    verify_reader(&mut reader, key, &sig, None, format)
+}
+
+/// 分离式签名文件，记录算法、密钥 ID 和 URL 安全的 base64 签名。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DetachedSignature {
+    pub algorithm: String,
+    pub key_id: String,
+    pub signature: String,
+}
+
+/// 签名并返回分离式签名文件的 JSON 内容。
+pub fn process_sign_detached(
+    input: &str,
+    key: &str,
+    format: TextSignFormat,
+) -> anyhow::Result<String> {
+    let mut reader = get_reader(input)?;
+    let signer = load_signer(key, format)?;
+    let signed = signer.sign(&mut reader)?;
+    let detached = DetachedSignature {
+        algorithm: format.to_string(),
+        key_id: signer.key_id(),
+        signature: URL_SAFE_NO_PAD.encode(&signed),
     };
-    Ok(verified)
+    Ok(serde_json::to_string_pretty(&detached)?)
 }
 
-pub fn process_gen_key(format: TextSignFormat) -> anyhow::Result<Vec<Vec<u8>>> {
+/// 按验证方指定的算法验证，签名文件记录的算法或密钥 ID 不一致时直接报错。
+pub fn process_verify_detached(
+    input: &str,
+    key: &str,
+    sig_file: &str,
+    format: TextSignFormat,
+) -> anyhow::Result<bool> {
+    let content = fs::read_to_string(sig_file)?;
+    let detached: DetachedSignature = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("签名文件 {} 格式错误: {}", sig_file, e))?;
+    check_algorithm(&detached.algorithm, format)?;
+    let sig = URL_SAFE_NO_PAD.decode(detached.signature.as_bytes())?;
+    let mut reader = get_reader(input)?;
+    verify_reader(&mut reader, key, &sig, Some(&detached.key_id), format)
+}
+
+/// 生成 minisign 兼容的签名，默认的 trusted comment 与 minisign 相同，记录时间戳和文件名。
+pub fn process_sign_minisign(
+    input: &str,
+    key: &str,
+    prehashed: bool,
+    trusted_comment: Option<&str>,
+) -> anyhow::Result<String> {
+    let trusted_comment = match trusted_comment {
+        Some(comment) => comment.to_string(),
+        None => {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let file = Path::new(input)
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            let hashed = if prehashed { "\thashed" } else { "" };
+            format!("timestamp:{}\tfile:{}{}", timestamp, file, hashed)
+        }
+    };
+    let mut reader = get_reader(input)?;
+    Ed25519Signer::load(key)?.sign_minisign(&mut reader, prehashed, &trusted_comment)
+}
+
+/// 验证 minisign 签名文件，通过时返回 trusted comment，签名无效时返回 `None`。
+pub fn process_verify_minisign(
+    input: &str,
+    key: &str,
+    minisig: &str,
+) -> anyhow::Result<Option<String>> {
+    let minisig = fs::read_to_string(minisig)?;
+    let mut reader = get_reader(input)?;
+    Ed25519Verifier::load(key)?.verify_minisign(&mut reader, &minisig)
+}
+
+// 算法只能由验证方决定：若采用签名文件中的算法，攻击者可以把公钥当作
+// blake3/hmac 的共享密钥来伪造签名
+pub(crate) fn check_algorithm(recorded: &str, format: TextSignFormat) -> anyhow::Result<()> {
+    if recorded != format.to_string() {
+        anyhow::bail!(
+            "签名文件记录的算法 {} 与指定的算法 {} 不一致",
+            recorded,
+            format
+        );
+    }
+    Ok(())
+}
+
+/// 按 `format` 加载签名方，同一实例既用于签名也用于取得密钥 ID，密钥文件只读取一次。
+pub(crate) fn load_signer(
+    key: &str,
+    format: TextSignFormat,
+) -> anyhow::Result<Box<dyn KeyedSigner>> {
+    Ok(match format {
+        TextSignFormat::Blake3 => Box::new(Blake3::load(key)?),
+        TextSignFormat::Ed25519 => Box::new(Ed25519Signer::load(key)?),
+        TextSignFormat::Ed25519ph => Box::new(Ed25519phSigner::load(key)?),
+        TextSignFormat::HmacSha256 => Box::new(HmacSha256::load(key)?),
+        TextSignFormat::HmacSha512 => Box::new(HmacSha512::load(key)?),
+    })
+}
+
+/// `key_id` 不为空时先检查与加载的密钥是否一致。
+pub(crate) fn verify_reader(
+    reader: &mut dyn Read,
+    key: &str,
+    sig: &[u8],
+    key_id: Option<&str>,
+    format: TextSignFormat,
+) -> anyhow::Result<bool> {
+    fn verify(
+        verifier: impl TextVerify + KeyId,
+        reader: &mut dyn Read,
+        sig: &[u8],
+        key_id: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        if let Some(key_id) = key_id {
+            if key_id != verifier.key_id() {
+                anyhow::bail!(
+                    "签名使用的密钥 {} 与提供的密钥 {} 不一致",
+                    key_id,
+                    verifier.key_id()
+                );
+            }
+        }
+        verifier.verify(reader, sig)
+    }
+
     match format {
-        TextSignFormat::Blake3 => Blake3::generate(),
-        TextSignFormat::Ed25519 => Ed25519Signer::generate(),
-        TextSignFormat::ChaCha20Poly1305 => ChaCha20Poly1305Aead::generate(),
+        TextSignFormat::Blake3 => verify(Blake3::load(key)?, reader, sig, key_id),
+        TextSignFormat::Ed25519 => verify(Ed25519Verifier::load(key)?, reader, sig, key_id),
+        TextSignFormat::Ed25519ph => verify(Ed25519phVerifier::load(key)?, reader, sig, key_id),
+        TextSignFormat::HmacSha256 => verify(HmacSha256::load(key)?, reader, sig, key_id),
+        TextSignFormat::HmacSha512 => verify(HmacSha512::load(key)?, reader, sig, key_id),
+    }
+}
+
+/// `key_format` 只对 ed25519 生效，其余算法仅支持 raw；指定 `passphrase` 时加密私钥文件。
+pub fn process_gen_key(
+    kind: TextKeyKind,
+    key_format: KeyFormat,
+    passphrase: Option<&str>,
+    rng: &mut CliRng,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    let ed25519 = matches!(
+        kind,
+        TextKeyKind::Sign(TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph)
+    );
+    if key_format != KeyFormat::Raw && !ed25519 {
+        anyhow::bail!("--key-format 仅支持 ed25519 密钥");
+    }
+    // 有标准加密方式的格式由 encode_ed25519 加密，raw 格式使用 rcli 自己的封装
+    if key_format != KeyFormat::Raw {
+        let key = SigningKey::generate(rng);
+        return encode_ed25519(&key, key_format, passphrase, rng);
+    }
+    let mut keys = match kind {
+        TextKeyKind::Sign(TextSignFormat::Blake3) => Blake3::generate(rng),
+        TextKeyKind::Sign(TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph) => {
+            encode_ed25519(&SigningKey::generate(rng), key_format, None, rng)
+        }
+        TextKeyKind::Sign(TextSignFormat::HmacSha256) => HmacSha256::generate(rng),
+        TextKeyKind::Sign(TextSignFormat::HmacSha512) => HmacSha512::generate(rng),
+        TextKeyKind::Cipher(AeadCipher::ChaCha20Poly1305) => ChaCha20Poly1305Aead::generate(rng),
+        TextKeyKind::Cipher(AeadCipher::XChaCha20Poly1305) => XChaCha20Poly1305Aead::generate(rng),
+        TextKeyKind::Cipher(AeadCipher::Aes256Gcm) => Aes256GcmAead::generate(rng),
+        TextKeyKind::Cipher(AeadCipher::Aes256GcmSiv) => Aes256GcmSivAead::generate(rng),
+        TextKeyKind::X25519 => AgeIdentities::generate(rng),
+    }?;
+    if let Some(passphrase) = passphrase {
+        keys[0] = wrap_key(&keys[0], passphrase, rng)?;
+    }
+    Ok(keys)
+}
+
+/// 加密 `input` 写入 `writer`，`binary` 为假时输出 URL 安全的 base64 文本。
+pub fn process_encrypt(
+    input: &str,
+    writer: &mut dyn Write,
+    key: &str,
+    cipher: AeadCipher,
+    aad: &[u8],
+    binary: bool,
+    rng: &mut CliRng,
+) -> anyhow::Result<()> {
+    match cipher {
+        AeadCipher::ChaCha20Poly1305 => encrypt_to(
+            &ChaCha20Poly1305Aead::load(key)?,
+            input,
+            writer,
+            aad,
+            binary,
+            rng,
+        ),
+        AeadCipher::XChaCha20Poly1305 => encrypt_to(
+            &XChaCha20Poly1305Aead::load(key)?,
+            input,
+            writer,
+            aad,
+            binary,
+            rng,
+        ),
+        AeadCipher::Aes256Gcm => {
+            encrypt_to(&Aes256GcmAead::load(key)?, input, writer, aad, binary, rng)
+        }
+        AeadCipher::Aes256GcmSiv => encrypt_to(
+            &Aes256GcmSivAead::load(key)?,
+            input,
+            writer,
+            aad,
+            binary,
+            rng,
+        ),
+    }
+}
+
+/// 解密时算法由密文头部决定，密钥文件只需包含 32 字节密钥。
+pub fn process_decrypt(
+    input: &str,
+    writer: &mut dyn Write,
+    key: &str,
+    aad: &[u8],
+    binary: bool,
+) -> anyhow::Result<()> {
+    let decryptor = ChaCha20Poly1305Aead::load(key)?;
+    decrypt_to(&decryptor, input, writer, aad, binary)
+}
+
+/// 使用口令加密，密钥由 Argon2id 派生。
+pub fn process_encrypt_with_passphrase(
+    input: &str,
+    writer: &mut dyn Write,
+    passphrase: &str,
+    cipher: AeadCipher,
+    aad: &[u8],
+    binary: bool,
+    rng: &mut CliRng,
+) -> anyhow::Result<()> {
+    let encryptor = PassphraseAead::new(passphrase, cipher);
+    encrypt_to(&encryptor, input, writer, aad, binary, rng)
+}
+
+pub fn process_decrypt_with_passphrase(
+    input: &str,
+    writer: &mut dyn Write,
+    passphrase: &str,
+    aad: &[u8],
+    binary: bool,
+) -> anyhow::Result<()> {
+    // 解密时使用密文头部中记录的算法
+    let decryptor = PassphraseAead::new(passphrase, AeadCipher::ChaCha20Poly1305);
+    decrypt_to(&decryptor, input, writer, aad, binary)
+}
+
+/// 使用 age 格式加密给 X25519 公钥，`binary` 为假时输出 ASCII armor。
+pub fn process_encrypt_to_recipients(
+    input: &str,
+    writer: &mut dyn Write,
+    recipients: &[String],
+    binary: bool,
+    rng: &mut CliRng,
+) -> anyhow::Result<()> {
+    let encryptor = AgeRecipients::parse(recipients)?;
+    let mut reader = get_reader(input)?;
+    if binary {
+        encryptor.encrypt(&mut reader, writer, &[], rng)?;
+    } else {
+        let mut armored = ArmoredWriter::wrap_output(&mut *writer, Format::AsciiArmor)?;
+        encryptor.encrypt(&mut reader, &mut armored, &[], rng)?;
+        armored.finish()?;
     }
+    writer.flush()?;
+    Ok(())
 }
 
-pub fn process_encrypt(input: &str, key: &str) -> anyhow::Result<String> {
+/// 使用 X25519 私钥解密 age 文件，二进制和 ASCII armor 会自动识别。
+pub fn process_decrypt_with_identities(
+    input: &str,
+    writer: &mut dyn Write,
+    identities: &[String],
+) -> anyhow::Result<()> {
+    let decryptor = AgeIdentities::load_all(identities)?;
     let mut reader = get_reader(input)?;
-    let encrypted = ChaCha20Poly1305Aead::load(key)?.encrypt(&mut reader)?;
-    Ok(encrypted)
+    decryptor.decrypt(&mut reader, writer, &[])?;
+    writer.flush()?;
+    Ok(())
 }
 
-pub fn process_decrypt(input: &str, key: &str) -> anyhow::Result<String> {
+fn encrypt_to(
+    encryptor: &impl Encryptor,
+    input: &str,
+    writer: &mut dyn Write,
+    aad: &[u8],
+    binary: bool,
+    rng: &mut CliRng,
+) -> anyhow::Result<()> {
+    let mut reader = get_reader(input)?;
+    if binary {
+        encryptor.encrypt(&mut reader, writer, aad, rng)?;
+    } else {
+        let mut encoder = EncoderWriter::new(&mut *writer, &URL_SAFE_NO_PAD);
+        encryptor.encrypt(&mut reader, &mut encoder, aad, rng)?;
+        writeln!(encoder.finish()?)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn decrypt_to(
+    decryptor: &impl Decryptor,
+    input: &str,
+    writer: &mut dyn Write,
+    aad: &[u8],
+    binary: bool,
+) -> anyhow::Result<()> {
     let mut reader = get_reader(input)?;
-    let decrypted = ChaCha20Poly1305Aead::load(key)?.decrypt(&mut reader)?;
-    Ok(decrypted)
+    if binary {
+        decryptor.decrypt(&mut reader, writer, aad)?;
+    } else {
+        let mut reader = decode_reader(reader, Base64Format::UrlSafe);
+        decryptor.decrypt(&mut reader, writer, aad).map_err(|e| {
+            match e.downcast_ref::<std::io::Error>() {
+                Some(io) if io.kind() == std::io::ErrorKind::InvalidData => {
+                    anyhow::anyhow!("密文不是合法的 base64，二进制密文请使用 --binary: {}", io)
+                }
+                _ => e,
+            }
+        })?;
+    }
+    writer.flush()?;
+    Ok(())
 }
 
 pub trait KeyLoader {
@@ -61,6 +404,18 @@ pub trait KeyLoader {
         Self: Sized;
 }
 
+/// 读取密钥文件，遇到口令保护的私钥时在终端提示输入口令并解开。
+pub fn read_key_file(path: impl AsRef<Path>) -> anyhow::Result<Vec<u8>> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    if !is_wrapped_key(&data) {
+        return Ok(data);
+    }
+    eprintln!("{} 已使用口令保护", path.display());
+    let passphrase = prompt_passphrase(false)?;
+    unwrap_key(&data, &passphrase)
+}
+
 pub trait TextSign {
     fn sign(&self, reader: &mut dyn Read) -> anyhow::Result<Vec<u8>>;
 }
@@ -69,18 +424,51 @@ pub trait TextVerify {
     fn verify(&self, reader: impl Read, sig: &[u8]) -> anyhow::Result<bool>;
 }
 
+/// 用于在签名文件中标识密钥，同一密钥的签名方与验证方得到相同的 ID。
+pub trait KeyId {
+    fn key_id(&self) -> String;
+}
+
+/// 能给出密钥 ID 的签名方，供分离式签名和清单签名使用。
+pub trait KeyedSigner: TextSign + KeyId {}
+
+impl<T: TextSign + KeyId> KeyedSigner for T {}
+
+// 公钥或共享密钥经 blake3 派生后取前 8 字节，不会泄露共享密钥
+fn derive_key_id(material: &[u8]) -> String {
+    let id = blake3::derive_key("rcli 2024 key id", material);
+    HEXLOWER_PERMISSIVE.encode(&id[..8])
+}
+
 pub trait KeyGenerator {
-    fn generate() -> anyhow::Result<Vec<Vec<u8>>>;
+    fn generate(rng: &mut CliRng) -> anyhow::Result<Vec<Vec<u8>>>;
 }
 
 pub trait Encryptor {
-    fn encrypt(&self, reader: &mut dyn Read) -> anyhow::Result<String>;
+    /// 流式加密，向 `writer` 写出原始的二进制密文；
+    /// `aad` 为附加数据，只参与认证、不写入密文，解密时必须提供相同的值；
+    /// nonce 和盐从 `rng` 中抽取
+    fn encrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+        rng: &mut CliRng,
+    ) -> anyhow::Result<()>;
 }
 
 pub trait Decryptor {
-    fn decrypt(&self, reader: &mut dyn Read) -> anyhow::Result<String>;
+    /// 流式解密原始的二进制密文，向 `writer` 写出明文
+    fn decrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+    ) -> anyhow::Result<()>;
 }
 
+/// 密钥文件为 64 个 hex 字符（可带结尾换行）；
+/// 旧版本生成的 32 字节原始密钥文件仍可读取。
 pub struct Blake3 {
     key: [u8; 32],
 }
@@ -91,64 +479,113 @@ impl Blake3 {
     }
 
     fn try_new(key: &[u8]) -> anyhow::Result<Self> {
-        let key = &key[..32];
-        let key = key.try_into()?;
-        Ok(Self::new(key))
+        if let Ok(key) = <[u8; 32]>::try_from(key) {
+            return Ok(Self::new(key));
+        }
+
+        let text = key.trim_ascii_end();
+        match text.len() {
+            // 旧版本的密钥由可打印字符组成，去掉结尾换行后恰好 32 字节
+            32 => Ok(Self::new(text.try_into()?)),
+            64 => {
+                let key = HEXLOWER_PERMISSIVE
+                    .decode(text)
+                    .map_err(|e| anyhow::anyhow!("blake3 密钥不是合法的 hex: {}", e))?;
+                Ok(Self::new(
+                    key.try_into().expect("64 个 hex 字符解码后一定是 32 字节"),
+                ))
+            }
+            _ => anyhow::bail!(
+                "blake3 密钥格式错误：需要 64 个 hex 字符，实际为 {} 字节",
+                key.len()
+            ),
+        }
     }
 }
 
 impl KeyGenerator for Blake3 {
-    fn generate() -> anyhow::Result<Vec<Vec<u8>>> {
-        let key = process_gen_pass(32, true, true, true, true)?;
-        let key = key.into_bytes();
+    fn generate(rng: &mut CliRng) -> anyhow::Result<Vec<Vec<u8>>> {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        let key = HEXLOWER_PERMISSIVE.encode(&key).into_bytes();
         Ok(vec![key])
     }
 }
 
 impl KeyLoader for Blake3 {
     fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
-        let key = fs::read(path)?;
+        let key = read_key_file(path)?;
         Self::try_new(&key)
     }
 }
 
+impl Blake3 {
+    // 增量计算，内存占用与输入大小无关
+    fn keyed_hash(&self, reader: &mut dyn Read) -> anyhow::Result<blake3::Hash> {
+        let mut hasher = blake3::Hasher::new_keyed(&self.key);
+        hasher.update_reader(reader)?;
+        Ok(hasher.finalize())
+    }
+}
+
+impl KeyId for Blake3 {
+    fn key_id(&self) -> String {
+        derive_key_id(&self.key)
+    }
+}
+
 impl TextSign for Blake3 {
     fn sign(&self, reader: &mut dyn Read) -> anyhow::Result<Vec<u8>> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        let signed = blake3::keyed_hash(&self.key, &buf).as_bytes().to_vec();
+        let signed = self.keyed_hash(reader)?.as_bytes().to_vec();
         Ok(signed)
     }
 }
 
 impl TextVerify for Blake3 {
     fn verify(&self, mut reader: impl Read, sig: &[u8]) -> anyhow::Result<bool> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        let hash = blake3::keyed_hash(&self.key, &buf);
-        let hash = hash.as_bytes();
-        Ok(hash == sig)
+        let hash = self.keyed_hash(&mut reader)?;
+        // blake3::Hash 与 32 字节切片的比较是常量时间的
+        Ok(hash == *sig)
     }
 }
 
 pub struct Ed25519Signer {
     key: SigningKey,
+    // minisign 签名中的密钥 ID，非 minisign 密钥由公钥派生
+    minisign_id: minisign::KeyId,
 }
 
 impl Ed25519Signer {
-    fn new(key: SigningKey) -> Self {
-        Self { key }
+    fn new(key: SigningKey, minisign_id: Option<minisign::KeyId>) -> Self {
+        let minisign_id =
+            minisign_id.unwrap_or_else(|| minisign::derive_key_id(&key.verifying_key()));
+        Self { key, minisign_id }
     }
     fn try_new(key: &[u8]) -> anyhow::Result<Self> {
-        let key = SigningKey::from_bytes(key.try_into()?);
-        Ok(Self::new(key))
+        let (key, minisign_id) = decode_signing_key_with_id(key)?;
+        Ok(Self::new(key, minisign_id))
+    }
+
+    /// 生成 minisign 的 `.minisig` 签名文件内容。
+    fn sign_minisign(
+        &self,
+        reader: &mut dyn Read,
+        prehashed: bool,
+        trusted_comment: &str,
+    ) -> anyhow::Result<String> {
+        minisign::sign(
+            &self.minisign_id,
+            &self.key,
+            reader,
+            prehashed,
+            trusted_comment,
+        )
     }
 }
 
 impl KeyGenerator for Ed25519Signer {
-    fn generate() -> anyhow::Result<Vec<Vec<u8>>> {
-        let mut csprng = OsRng;
-        let signing_key: SigningKey = SigningKey::generate(&mut csprng);
+    fn generate(rng: &mut CliRng) -> anyhow::Result<Vec<Vec<u8>>> {
+        let signing_key: SigningKey = SigningKey::generate(rng);
         let pk = signing_key.verifying_key().to_bytes().to_vec();
         let signing_key = signing_key.to_bytes().to_vec();
         Ok(vec![signing_key, pk])
@@ -157,33 +594,56 @@ impl KeyGenerator for Ed25519Signer {
 
 impl KeyLoader for Ed25519Signer {
     fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
-        let key = fs::read(path)?;
+        let key = read_key_file(path)?;
         Self::try_new(&key)
     }
 }
 
 pub struct Ed25519Verifier {
     key: VerifyingKey,
+    minisign_id: minisign::KeyId,
 }
 
 impl Ed25519Verifier {
-    fn new(key: VerifyingKey) -> Self {
-        Self { key }
+    fn new(key: VerifyingKey, minisign_id: Option<minisign::KeyId>) -> Self {
+        let minisign_id = minisign_id.unwrap_or_else(|| minisign::derive_key_id(&key));
+        Self { key, minisign_id }
     }
 
     fn try_new(key: &[u8]) -> anyhow::Result<Self> {
-        let key = VerifyingKey::from_bytes(key.try_into()?)?;
-        Ok(Self::new(key))
+        let (key, minisign_id) = decode_verifying_key_with_id(key)?;
+        Ok(Self::new(key, minisign_id))
+    }
+
+    /// 验证 `.minisig` 签名文件，通过时返回其中的 trusted comment。
+    fn verify_minisign(
+        &self,
+        reader: &mut dyn Read,
+        minisig: &str,
+    ) -> anyhow::Result<Option<String>> {
+        minisign::verify(&self.minisign_id, &self.key, reader, minisig)
     }
 }
 
 impl KeyLoader for Ed25519Verifier {
     fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
-        let key = fs::read(path)?;
+        let key = read_key_file(path)?;
         Self::try_new(&key)
     }
 }
 
+impl KeyId for Ed25519Signer {
+    fn key_id(&self) -> String {
+        derive_key_id(self.key.verifying_key().as_bytes())
+    }
+}
+
+impl KeyId for Ed25519Verifier {
+    fn key_id(&self) -> String {
+        derive_key_id(self.key.as_bytes())
+    }
+}
+
 impl TextSign for Ed25519Signer {
     fn sign(&self, reader: &mut dyn Read) -> anyhow::Result<Vec<u8>> {
         let mut buf = Vec::new();
@@ -203,77 +663,860 @@ impl TextVerify for Ed25519Verifier {
     }
 }
 
+/// HMAC 签名，密钥文件内容即为密钥本身（与 webhook 平台下发的 secret 相同），
+/// 末尾的换行符会被忽略。
+pub struct HmacSigner<D> {
+    key: Vec<u8>,
+    _digest: PhantomData<D>,
+}
+
+pub type HmacSha256 = HmacSigner<Sha256>;
+pub type HmacSha512 = HmacSigner<Sha512>;
+
+impl<D> HmacSigner<D>
+where
+    D: Digest + BlockSizeUser,
+{
+    fn new(key: Vec<u8>) -> Self {
+        Self {
+            key,
+            _digest: PhantomData,
+        }
+    }
+
+    fn try_new(key: &[u8]) -> anyhow::Result<Self> {
+        let key = key
+            .strip_suffix(b"\n")
+            .map(|key| key.strip_suffix(b"\r").unwrap_or(key))
+            .unwrap_or(key);
+        if key.is_empty() {
+            anyhow::bail!("HMAC 密钥不能为空");
+        }
+        Ok(Self::new(key.to_vec()))
+    }
+
+    fn mac(&self, reader: &mut dyn Read) -> anyhow::Result<SimpleHmac<D>> {
+        let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(&self.key)?;
+        update_from_reader(reader, |chunk| mac.update(chunk))?;
+        Ok(mac)
+    }
+}
+
+impl<D> KeyGenerator for HmacSigner<D> {
+    fn generate(rng: &mut CliRng) -> anyhow::Result<Vec<Vec<u8>>> {
+        // 以 hex 文本保存 256 位随机密钥，便于粘贴到 webhook 平台
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        Ok(vec![HEXLOWER_PERMISSIVE.encode(&key).into_bytes()])
+    }
+}
+
+impl<D> KeyLoader for HmacSigner<D>
+where
+    D: Digest + BlockSizeUser,
+{
+    fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let key = read_key_file(path)?;
+        Self::try_new(&key)
+    }
+}
+
+impl<D> KeyId for HmacSigner<D> {
+    fn key_id(&self) -> String {
+        derive_key_id(&self.key)
+    }
+}
+
+impl<D> TextSign for HmacSigner<D>
+where
+    D: Digest + BlockSizeUser,
+{
+    fn sign(&self, reader: &mut dyn Read) -> anyhow::Result<Vec<u8>> {
+        Ok(self.mac(reader)?.finalize().into_bytes().to_vec())
+    }
+}
+
+impl<D> TextVerify for HmacSigner<D>
+where
+    D: Digest + BlockSizeUser,
+{
+    fn verify(&self, mut reader: impl Read, sig: &[u8]) -> anyhow::Result<bool> {
+        // verify_slice 使用常量时间比较
+        Ok(self.mac(&mut reader)?.verify_slice(sig).is_ok())
+    }
+}
+
+/// Ed25519ph（RFC 8032 预哈希模式）：先对输入做流式 SHA-512，适合签名大文件。
+/// 与纯 Ed25519 使用同一套密钥，但签名互不兼容。
+pub struct Ed25519phSigner {
+    key: SigningKey,
+}
+
+pub struct Ed25519phVerifier {
+    key: VerifyingKey,
+}
+
+fn sha512_prehash(reader: &mut dyn Read) -> anyhow::Result<Sha512> {
+    let mut hasher = Sha512::new();
+    update_from_reader(reader, |chunk| hasher.update(chunk))?;
+    Ok(hasher)
+}
+
+impl KeyLoader for Ed25519phSigner {
+    fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let key = Ed25519Signer::load(path)?.key;
+        Ok(Self { key })
+    }
+}
+
+impl KeyLoader for Ed25519phVerifier {
+    fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let key = Ed25519Verifier::load(path)?.key;
+        Ok(Self { key })
+    }
+}
+
+impl KeyId for Ed25519phSigner {
+    fn key_id(&self) -> String {
+        derive_key_id(self.key.verifying_key().as_bytes())
+    }
+}
+
+impl KeyId for Ed25519phVerifier {
+    fn key_id(&self) -> String {
+        derive_key_id(self.key.as_bytes())
+    }
+}
+
+impl TextSign for Ed25519phSigner {
+    fn sign(&self, reader: &mut dyn Read) -> anyhow::Result<Vec<u8>> {
+        let prehashed = sha512_prehash(reader)?;
+        let sig = self.key.sign_prehashed(prehashed, None)?;
+        Ok(sig.to_bytes().to_vec())
+    }
+}
+
+impl TextVerify for Ed25519phVerifier {
+    fn verify(&self, mut reader: impl Read, sig: &[u8]) -> anyhow::Result<bool> {
+        let prehashed = sha512_prehash(&mut reader)?;
+        let signature = Signature::from_bytes(sig.try_into()?);
+        Ok(self
+            .key
+            .verify_prehashed(prehashed, None, &signature)
+            .is_ok())
+    }
+}
+
+/// 可用于 [`encrypt_stream`] 的 AEAD 算法，密钥均为 32 字节。
+pub trait StreamCipher: Aead + KeyInit {
+    const CIPHER: AeadCipher;
+}
+
+impl StreamCipher for ChaCha20Poly1305 {
+    const CIPHER: AeadCipher = AeadCipher::ChaCha20Poly1305;
+}
+
+impl StreamCipher for XChaCha20Poly1305 {
+    const CIPHER: AeadCipher = AeadCipher::XChaCha20Poly1305;
+}
+
+impl StreamCipher for Aes256Gcm {
+    const CIPHER: AeadCipher = AeadCipher::Aes256Gcm;
+}
+
+impl StreamCipher for Aes256GcmSiv {
+    const CIPHER: AeadCipher = AeadCipher::Aes256GcmSiv;
+}
+
+// 密文头部的版本号，其后紧跟算法编号；最早的无版本号格式见 [`ChaCha20Poly1305Aead`]
+const CIPHERTEXT_VERSION: u8 = 1;
+const PASSPHRASE_VERSION: u8 = 2;
+const TAG_SIZE: usize = 16;
+const SALT_SIZE: usize = 16;
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+// 写入头部的算法编号，一经发布不可更改
+fn cipher_id(cipher: AeadCipher) -> u8 {
+    match cipher {
+        AeadCipher::ChaCha20Poly1305 => 1,
+        AeadCipher::XChaCha20Poly1305 => 2,
+        AeadCipher::Aes256Gcm => 3,
+        AeadCipher::Aes256GcmSiv => 4,
+    }
+}
+
+fn cipher_from_id(id: u8) -> anyhow::Result<AeadCipher> {
+    match id {
+        1 => Ok(AeadCipher::ChaCha20Poly1305),
+        2 => Ok(AeadCipher::XChaCha20Poly1305),
+        3 => Ok(AeadCipher::Aes256Gcm),
+        4 => Ok(AeadCipher::Aes256GcmSiv),
+        _ => anyhow::bail!("不支持的加密算法编号 {}", id),
+    }
+}
+
+/// 密文格式为 `版本号(1) || 算法(1) || nonce 前缀 || 分块密文`，见 [`encrypt_stream`]。
+/// 解密时按头部记录的算法自动选择，与密钥对象的类型无关。
+pub struct StreamAead<A> {
+    key: [u8; 32],
+    _cipher: PhantomData<A>,
+}
+
+pub type XChaCha20Poly1305Aead = StreamAead<XChaCha20Poly1305>;
+pub type Aes256GcmAead = StreamAead<Aes256Gcm>;
+pub type Aes256GcmSivAead = StreamAead<Aes256GcmSiv>;
+
+impl<A: StreamCipher> StreamAead<A> {
+    fn new(key: [u8; 32]) -> Self {
+        Self {
+            key,
+            _cipher: PhantomData,
+        }
+    }
+
+    fn try_new(key: &[u8]) -> anyhow::Result<Self> {
+        let key = key.try_into().map_err(|_| {
+            anyhow::anyhow!(
+                "{} 密钥长度错误：需要 32 字节，实际为 {} 字节",
+                A::CIPHER,
+                key.len()
+            )
+        })?;
+        Ok(Self::new(key))
+    }
+}
+
+impl<A: StreamCipher> KeyLoader for StreamAead<A> {
+    fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let key = read_key_file(path)?;
+        Self::try_new(&key)
+    }
+}
+
+impl<A: StreamCipher> KeyGenerator for StreamAead<A> {
+    fn generate(rng: &mut CliRng) -> anyhow::Result<Vec<Vec<u8>>> {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        Ok(vec![key.to_vec()])
+    }
+}
+
+impl<A: StreamCipher> Encryptor for StreamAead<A> {
+    fn encrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+        rng: &mut CliRng,
+    ) -> anyhow::Result<()> {
+        let header = [CIPHERTEXT_VERSION, cipher_id(A::CIPHER)];
+        encrypt_stream::<A>(&self.key, &header, aad, reader, writer, rng)
+    }
+}
+
+impl<A: StreamCipher> Decryptor for StreamAead<A> {
+    fn decrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+    ) -> anyhow::Result<()> {
+        let version = read_version(reader)?;
+        decrypt_keyed(&self.key, version, aad, reader, writer)
+    }
+}
+
+// 按版本号解密密钥文件模式的密文，`reader` 已经读走了版本号
+fn decrypt_keyed(
+    key: &[u8; 32],
+    version: u8,
+    aad: &[u8],
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    match version {
+        CIPHERTEXT_VERSION => {
+            let mut id = [0u8; 1];
+            reader
+                .read_exact(&mut id)
+                .map_err(|_| anyhow::anyhow!("密文被截断"))?;
+            let header = [version, id[0]];
+            let cipher = cipher_from_id(id[0])?;
+            decrypt_stream_with(cipher, key, &header, aad, reader, writer)
+        }
+        PASSPHRASE_VERSION => {
+            anyhow::bail!("该密文由口令加密，请使用 --passphrase 解密")
+        }
+        _ => anyhow::bail!("解密密文失败：不支持的密文版本 {}", version),
+    }
+}
+
+/// 默认的 ChaCha20-Poly1305 密钥，密文格式同 [`StreamAead`]。
+///
+/// 旧版本的密钥文件在 32 字节密钥后附带一个固定 nonce，
+/// 仅用于解密旧版本产生的无版本号密文。
 pub struct ChaCha20Poly1305Aead {
     key: [u8; 32],
-    nonce: Nonce,
+    legacy_nonce: Option<Nonce>,
 }
 
 impl ChaCha20Poly1305Aead {
-    fn new(key: [u8; 32], nonce: Nonce) -> Self {
-        Self { key, nonce }
+    fn new(key: [u8; 32], legacy_nonce: Option<Nonce>) -> Self {
+        Self { key, legacy_nonce }
     }
 
     fn try_new(key: &[u8]) -> anyhow::Result<Self> {
-        // let encrypted = URL_SAFE_NO_PAD.decode(&key)?;
-        let (key, nonce) = key.split_at(32);
-        let nonce = Nonce::from_slice(nonce);
-        Ok(Self::new(key.try_into()?, nonce.to_owned()))
+        match key.len() {
+            32 => Ok(Self::new(key.try_into()?, None)),
+            44 => {
+                let (key, nonce) = key.split_at(32);
+                Ok(Self::new(
+                    key.try_into()?,
+                    Some(Nonce::from_slice(nonce).to_owned()),
+                ))
+            }
+            n => anyhow::bail!(
+                "chacha20poly1305 密钥长度错误：需要 32 字节，实际为 {} 字节",
+                n
+            ),
+        }
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(&self.key.into())
     }
 }
 
 impl KeyLoader for ChaCha20Poly1305Aead {
     fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
-        let key = fs::read(path)?;
+        let key = read_key_file(path)?;
         Self::try_new(&key)
     }
 }
 
 impl KeyGenerator for ChaCha20Poly1305Aead {
-    fn generate() -> anyhow::Result<Vec<Vec<u8>>> {
-        let key = ChaCha20Poly1305::generate_key(&mut ChaCha20Poly1305AeadOsRng);
-        let nonce = ChaCha20Poly1305::generate_nonce(&mut ChaCha20Poly1305AeadOsRng); // 96-bits; unique per message
-        let mut encrypted = key.to_vec();
-        encrypted.extend_from_slice(&nonce);
-
-        Ok(vec![encrypted])
+    fn generate(rng: &mut CliRng) -> anyhow::Result<Vec<Vec<u8>>> {
+        let key = ChaCha20Poly1305::generate_key(&mut *rng);
+        Ok(vec![key.to_vec()])
     }
 }
 
 impl Encryptor for ChaCha20Poly1305Aead {
-    fn encrypt(&self, reader: &mut dyn Read) -> anyhow::Result<String> {
+    fn encrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+        rng: &mut CliRng,
+    ) -> anyhow::Result<()> {
+        StreamAead::<ChaCha20Poly1305>::new(self.key).encrypt(reader, writer, aad, rng)
+    }
+}
+
+impl Decryptor for ChaCha20Poly1305Aead {
+    fn decrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+    ) -> anyhow::Result<()> {
+        let Some(nonce) = &self.legacy_nonce else {
+            let version = read_version(reader)?;
+            return decrypt_keyed(&self.key, version, aad, reader, writer);
+        };
+
+        // 旧密文没有版本号，首字节可能恰好等于版本号，只能整块读入后先按新格式尝试，失败后再回退；
+        // 旧格式本身就是整块的 AEAD，回退时无论如何都需要完整的密文
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
+        let mut plaintext = Vec::new();
+        let first_err = match buf.split_first() {
+            Some((&version, rest)) => {
+                match decrypt_keyed(&self.key, version, aad, &mut &rest[..], &mut plaintext) {
+                    Ok(()) => {
+                        writer.write_all(&plaintext)?;
+                        return Ok(());
+                    }
+                    Err(e) => Some(e),
+                }
+            }
+            None => None,
+        };
+        let payload = Payload { msg: &buf, aad };
+        let Ok(plaintext) = self.cipher().decrypt(nonce, payload) else {
+            // 新格式的错误信息更具体（如密文被截断），优先返回
+            return Err(match first_err {
+                Some(e) => e.context("按旧版无版本号的格式解密同样失败"),
+                None => anyhow::anyhow!("解密密文失败：密钥错误或密文已损坏"),
+            });
+        };
+        writer.write_all(&plaintext)?;
+        Ok(())
+    }
+}
+
+/// 口令模式，密文格式为
+/// `版本号(1) || 算法(1) || m_cost(4) || t_cost(4) || p_cost(4) || salt(16) || nonce 前缀 || 分块密文`，
+/// 整数均为小端序。
+pub struct PassphraseAead {
+    passphrase: String,
+    params: argon2::Params,
+    cipher: AeadCipher,
+}
 
-        let cipher = ChaCha20Poly1305::new_from_slice(&self.key)?;
-        let ciphertext = cipher.encrypt(&self.nonce, buf.as_ref()).unwrap();
+// Argon2 参数与 salt 的长度
+const KDF_PARAMS_SIZE: usize = 12 + SALT_SIZE;
+// 解密时允许的 Argon2 参数上限，防止伪造的头部耗尽内存或 CPU
+const MAX_M_COST: u32 = 1 << 21;
+const MAX_T_COST: u32 = 64;
+const MAX_P_COST: u32 = 16;
 
-        let ciphertext = URL_SAFE_NO_PAD.encode(&ciphertext);
+impl PassphraseAead {
+    fn new(passphrase: &str, cipher: AeadCipher) -> Self {
+        Self::with_params(passphrase, argon2::Params::default(), cipher)
+    }
+
+    fn with_params(passphrase: &str, params: argon2::Params, cipher: AeadCipher) -> Self {
+        Self {
+            passphrase: passphrase.to_string(),
+            params,
+            cipher,
+        }
+    }
 
-        Ok(ciphertext)
+    fn derive_key(&self, params: argon2::Params, salt: &[u8]) -> anyhow::Result<[u8; 32]> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("派生密钥失败: {}", e))?;
+        Ok(key)
+    }
+
+    // 从头部的 `m_cost || t_cost || p_cost || salt` 解析 Argon2 参数并派生密钥
+    fn derive_key_from_header(&self, kdf: &[u8]) -> anyhow::Result<[u8; 32]> {
+        let cost = |i: usize| u32::from_le_bytes(kdf[i * 4..i * 4 + 4].try_into().unwrap());
+        let (m_cost, t_cost, p_cost) = (cost(0), cost(1), cost(2));
+        if m_cost > MAX_M_COST || t_cost > MAX_T_COST || p_cost > MAX_P_COST {
+            anyhow::bail!(
+                "密文中的 Argon2 参数过大：m={}, t={}, p={}",
+                m_cost,
+                t_cost,
+                p_cost
+            );
+        }
+        let params = argon2::Params::new(m_cost, t_cost, p_cost, Some(32))
+            .map_err(|e| anyhow::anyhow!("密文中的 Argon2 参数不合法: {}", e))?;
+        self.derive_key(params, &kdf[12..])
     }
 }
 
-impl Decryptor for ChaCha20Poly1305Aead {
-    fn decrypt(&self, reader: &mut dyn Read) -> anyhow::Result<String> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        let buf = buf.trim_ascii();
-        let buf = URL_SAFE_NO_PAD.decode(buf)?;
+impl Encryptor for PassphraseAead {
+    fn encrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+        rng: &mut CliRng,
+    ) -> anyhow::Result<()> {
+        let mut salt = [0u8; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+
+        let mut header = Vec::with_capacity(2 + KDF_PARAMS_SIZE);
+        header.push(PASSPHRASE_VERSION);
+        header.push(cipher_id(self.cipher));
+        header.extend_from_slice(&self.params.m_cost().to_le_bytes());
+        header.extend_from_slice(&self.params.t_cost().to_le_bytes());
+        header.extend_from_slice(&self.params.p_cost().to_le_bytes());
+        header.extend_from_slice(&salt);
 
-        let cipher = ChaCha20Poly1305::new_from_slice(&self.key)?;
-        let plaintext = cipher
-            .decrypt(&self.nonce, buf.as_ref())
-            .map_err(|e| anyhow::anyhow!("解密密文失败: {:?}", e))?;
+        let key = self.derive_key(self.params.clone(), &salt)?;
+        encrypt_stream_with(self.cipher, &key, &header, aad, reader, writer, rng)
+    }
+}
 
-        Ok(String::from_utf8(plaintext).unwrap())
+impl Decryptor for PassphraseAead {
+    fn decrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+    ) -> anyhow::Result<()> {
+        let version = read_version(reader)?;
+        if version != PASSPHRASE_VERSION {
+            anyhow::bail!("密文不是口令加密格式");
+        }
+        let mut header = [0u8; 2 + KDF_PARAMS_SIZE];
+        header[0] = version;
+        reader
+            .read_exact(&mut header[1..])
+            .map_err(|_| anyhow::anyhow!("密文被截断"))?;
+        let cipher = cipher_from_id(header[1])?;
+        let key = self.derive_key_from_header(&header[2..])?;
+        decrypt_stream_with(cipher, &key, &header, aad, reader, writer)
     }
 }
 
+/// 使用 age 格式（<https://age-encryption.org/v1>）加密给一个或多个 X25519 公钥，
+/// 生成的文件可以直接用 `age -d` 解密，反之亦然。
+pub struct AgeRecipients {
+    recipients: Vec<x25519::Recipient>,
+}
+
+impl AgeRecipients {
+    /// 每一项可以是 `age1...` 公钥，也可以是每行一个公钥的文件，`#` 开头的行为注释。
+    fn parse(values: &[String]) -> anyhow::Result<Self> {
+        let mut recipients = Vec::new();
+        for value in values {
+            if value.starts_with("age1") {
+                recipients.push(parse_age_key(value)?);
+                continue;
+            }
+            let content = fs::read_to_string(value)
+                .map_err(|e| anyhow::anyhow!("无法读取公钥文件 {}: {}", value, e))?;
+            for line in age_key_lines(&content) {
+                recipients.push(parse_age_key(line)?);
+            }
+        }
+        if recipients.is_empty() {
+            anyhow::bail!("至少需要一个接收者公钥");
+        }
+        Ok(Self { recipients })
+    }
+}
+
+impl Encryptor for AgeRecipients {
+    fn encrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+        // age 在内部使用系统随机数生成文件密钥
+        _rng: &mut CliRng,
+    ) -> anyhow::Result<()> {
+        if !aad.is_empty() {
+            anyhow::bail!("age 格式不支持附加数据");
+        }
+        let recipients = self.recipients.iter().map(|r| r as &dyn age::Recipient);
+        let mut output = age::Encryptor::with_recipients(recipients)?.wrap_output(writer)?;
+        std::io::copy(reader, &mut output)?;
+        output.finish()?;
+        Ok(())
+    }
+}
+
+const WRAPPED_KEY_BEGIN: &str = "-----BEGIN RCLI ENCRYPTED KEY-----";
+const WRAPPED_KEY_END: &str = "-----END RCLI ENCRYPTED KEY-----";
+
+/// 用口令包装私钥：内容为口令模式的密文，按 64 字符一行的 base64 存成 PEM 风格的文本。
+pub fn wrap_key(key: &[u8], passphrase: &str, rng: &mut CliRng) -> anyhow::Result<Vec<u8>> {
+    wrap_key_with(
+        &PassphraseAead::new(passphrase, AeadCipher::ChaCha20Poly1305),
+        key,
+        rng,
+    )
+}
+
+fn wrap_key_with(aead: &PassphraseAead, key: &[u8], rng: &mut CliRng) -> anyhow::Result<Vec<u8>> {
+    let mut ciphertext = Vec::new();
+    aead.encrypt(&mut &key[..], &mut ciphertext, &[], rng)?;
+    let encoded = STANDARD.encode(ciphertext);
+    let mut wrapped = format!("{}\n", WRAPPED_KEY_BEGIN);
+    for line in encoded.as_bytes().chunks(64) {
+        // base64 输出只有 ASCII 字符
+        wrapped.push_str(std::str::from_utf8(line)?);
+        wrapped.push('\n');
+    }
+    wrapped.push_str(WRAPPED_KEY_END);
+    wrapped.push('\n');
+    Ok(wrapped.into_bytes())
+}
+
+fn is_wrapped_key(data: &[u8]) -> bool {
+    data.trim_ascii_start()
+        .starts_with(WRAPPED_KEY_BEGIN.as_bytes())
+}
+
+/// 解开 [`wrap_key`] 包装的私钥，口令错误时返回错误。
+pub fn unwrap_key(data: &[u8], passphrase: &str) -> anyhow::Result<Vec<u8>> {
+    let text = std::str::from_utf8(data)?.trim();
+    let body = text
+        .strip_prefix(WRAPPED_KEY_BEGIN)
+        .and_then(|rest| rest.strip_suffix(WRAPPED_KEY_END))
+        .ok_or_else(|| anyhow::anyhow!("口令保护的密钥文件格式错误"))?;
+    let body: String = body.split_whitespace().collect();
+    let ciphertext = STANDARD
+        .decode(body)
+        .map_err(|_| anyhow::anyhow!("口令保护的密钥文件格式错误"))?;
+    let mut key = Vec::new();
+    PassphraseAead::new(passphrase, AeadCipher::ChaCha20Poly1305)
+        .decrypt(&mut &ciphertext[..], &mut key, &[])
+        .map_err(|_| anyhow::anyhow!("解开私钥失败：口令错误或文件已损坏"))?;
+    Ok(key)
+}
+
+/// age 格式的 X25519 私钥，密钥文件与 `age-keygen` 的输出兼容。
+pub struct AgeIdentities {
+    identities: Vec<x25519::Identity>,
+}
+
+impl AgeIdentities {
+    fn load_all(paths: &[String]) -> anyhow::Result<Self> {
+        let mut identities = Vec::new();
+        for path in paths {
+            let content = read_key_file(path)
+                .map_err(|e| anyhow::anyhow!("无法读取私钥文件 {}: {}", path, e))?;
+            let content = String::from_utf8(content)?;
+            for line in age_key_lines(&content) {
+                identities.push(parse_age_key(line)?);
+            }
+        }
+        if identities.is_empty() {
+            anyhow::bail!("私钥文件中没有找到 AGE-SECRET-KEY");
+        }
+        Ok(Self { identities })
+    }
+}
+
+impl KeyGenerator for AgeIdentities {
+    fn generate(rng: &mut CliRng) -> anyhow::Result<Vec<Vec<u8>>> {
+        let mut secret = [0u8; 32];
+        rng.fill_bytes(&mut secret);
+        // 与 age 一致：用小写 HRP 编码后整体转成大写
+        let encoded =
+            bech32::encode("age-secret-key-", secret.to_base32(), Variant::Bech32)?.to_uppercase();
+        let identity = parse_age_key::<x25519::Identity>(&encoded)?;
+        let recipient = identity.to_public().to_string();
+
+        let sk = format!("# public key: {}\n{}\n", recipient, encoded);
+        let pk = format!("{}\n", recipient);
+        Ok(vec![sk.into_bytes(), pk.into_bytes()])
+    }
+}
+
+impl Decryptor for AgeIdentities {
+    fn decrypt(
+        &self,
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        aad: &[u8],
+    ) -> anyhow::Result<()> {
+        if !aad.is_empty() {
+            anyhow::bail!("age 格式不支持附加数据");
+        }
+        // 自动识别二进制和 ASCII armor 两种形式
+        let decryptor = age::Decryptor::new(ArmoredReader::new(BufReader::new(reader)))?;
+        if decryptor.is_scrypt() {
+            anyhow::bail!("该文件由 age 口令加密，不支持使用私钥解密");
+        }
+        let identities = self.identities.iter().map(|i| i as &dyn age::Identity);
+        let mut plaintext = decryptor.decrypt(identities)?;
+        std::io::copy(&mut plaintext, writer)?;
+        Ok(())
+    }
+}
+
+fn age_key_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn parse_age_key<T: FromStr<Err = &'static str>>(key: &str) -> anyhow::Result<T> {
+    key.parse()
+        .map_err(|e| anyhow::anyhow!("无效的 age 密钥: {}", e))
+}
+
+fn read_version(reader: &mut dyn Read) -> anyhow::Result<u8> {
+    let mut version = [0u8; 1];
+    reader
+        .read_exact(&mut version)
+        .map_err(|_| anyhow::anyhow!("密文为空"))?;
+    Ok(version[0])
+}
+
+fn encrypt_stream_with(
+    cipher: AeadCipher,
+    key: &[u8; 32],
+    header: &[u8],
+    aad: &[u8],
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    rng: &mut CliRng,
+) -> anyhow::Result<()> {
+    match cipher {
+        AeadCipher::ChaCha20Poly1305 => {
+            encrypt_stream::<ChaCha20Poly1305>(key, header, aad, reader, writer, rng)
+        }
+        AeadCipher::XChaCha20Poly1305 => {
+            encrypt_stream::<XChaCha20Poly1305>(key, header, aad, reader, writer, rng)
+        }
+        AeadCipher::Aes256Gcm => encrypt_stream::<Aes256Gcm>(key, header, aad, reader, writer, rng),
+        AeadCipher::Aes256GcmSiv => {
+            encrypt_stream::<Aes256GcmSiv>(key, header, aad, reader, writer, rng)
+        }
+    }
+}
+
+fn decrypt_stream_with(
+    cipher: AeadCipher,
+    key: &[u8; 32],
+    header: &[u8],
+    aad: &[u8],
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    match cipher {
+        AeadCipher::ChaCha20Poly1305 => {
+            decrypt_stream::<ChaCha20Poly1305>(key, header, aad, reader, writer)
+        }
+        AeadCipher::XChaCha20Poly1305 => {
+            decrypt_stream::<XChaCha20Poly1305>(key, header, aad, reader, writer)
+        }
+        AeadCipher::Aes256Gcm => decrypt_stream::<Aes256Gcm>(key, header, aad, reader, writer),
+        AeadCipher::Aes256GcmSiv => {
+            decrypt_stream::<Aes256GcmSiv>(key, header, aad, reader, writer)
+        }
+    }
+}
+
+/// STREAM 的 nonce：`前缀 || 块序号(4, 大端) || 末块标记(1)`，与 `aead::stream::StreamBE32` 相同。
+struct StreamNonce {
+    prefix: Vec<u8>,
+    counter: u32,
+}
+
+impl StreamNonce {
+    // 前缀长度为算法 nonce 长度减 5，ChaCha20/AES-GCM 为 7 字节，XChaCha20 为 19 字节
+    fn prefix_size<A: AeadCore>() -> usize {
+        A::NonceSize::USIZE - 5
+    }
+
+    fn next(&mut self, last: bool) -> anyhow::Result<Vec<u8>> {
+        let nonce = [&self.prefix[..], &self.counter.to_be_bytes(), &[last as u8]].concat();
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| anyhow::anyhow!("分块数量超出上限"))?;
+        Ok(nonce)
+    }
+}
+
+/// STREAM 分块加密（Hoang 等人的在线 AEAD 构造）：写出 `header || nonce 前缀` 后，
+/// 明文按 64 KiB 分块独立加密，nonce 见 [`StreamNonce`]，附加数据为 `header || nonce 前缀 || aad`。
+/// 块序号防止重排，末块标记防止截断，内存占用与输入大小无关。
+fn encrypt_stream<A: Aead + KeyInit>(
+    key: &[u8; 32],
+    header: &[u8],
+    aad: &[u8],
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    rng: &mut CliRng,
+) -> anyhow::Result<()> {
+    let cipher = A::new_from_slice(key)?;
+    let mut prefix = vec![0u8; StreamNonce::prefix_size::<A>()];
+    rng.fill_bytes(&mut prefix);
+    writer.write_all(header)?;
+    writer.write_all(&prefix)?;
+    let aad = [header, &prefix, aad].concat();
+
+    let mut nonce = StreamNonce { prefix, counter: 0 };
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut next = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut n = read_full(reader, &mut buf)?;
+    loop {
+        // 只有读到下一块才能确定当前块不是最后一块
+        let m = if n == STREAM_CHUNK_SIZE {
+            read_full(reader, &mut next)?
+        } else {
+            0
+        };
+        let payload = Payload {
+            msg: &buf[..n],
+            aad: &aad,
+        };
+        let chunk = cipher
+            .encrypt(GenericArray::from_slice(&nonce.next(m == 0)?), payload)
+            .map_err(|e| anyhow::anyhow!("加密失败: {:?}", e))?;
+        writer.write_all(&chunk)?;
+        if m == 0 {
+            return Ok(());
+        }
+        std::mem::swap(&mut buf, &mut next);
+        n = m;
+    }
+}
+
+/// [`encrypt_stream`] 的逆过程，`header` 为调用方已经读取的头部。
+/// 每块在认证通过后才写出，截断或重排会在对应的块上报错。
+fn decrypt_stream<A: Aead + KeyInit>(
+    key: &[u8; 32],
+    header: &[u8],
+    aad: &[u8],
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let cipher = A::new_from_slice(key)?;
+    let mut prefix = vec![0u8; StreamNonce::prefix_size::<A>()];
+    reader
+        .read_exact(&mut prefix)
+        .map_err(|_| anyhow::anyhow!("密文被截断"))?;
+    let aad = [header, &prefix, aad].concat();
+
+    let mut nonce = StreamNonce { prefix, counter: 0 };
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE + TAG_SIZE];
+    let mut next = vec![0u8; STREAM_CHUNK_SIZE + TAG_SIZE];
+    let mut n = read_full(reader, &mut buf)?;
+    loop {
+        let m = if n == buf.len() {
+            read_full(reader, &mut next)?
+        } else {
+            0
+        };
+        let payload = Payload {
+            msg: &buf[..n],
+            aad: &aad,
+        };
+        let chunk = cipher
+            .decrypt(GenericArray::from_slice(&nonce.next(m == 0)?), payload)
+            .map_err(|_| {
+                if m == 0 {
+                    anyhow::anyhow!("解密密文失败：密钥错误、密文已损坏或被截断")
+                } else {
+                    anyhow::anyhow!("解密密文失败：密钥错误或密文已损坏")
+                }
+            })?;
+        writer.write_all(&chunk)?;
+        if m == 0 {
+            return Ok(());
+        }
+        std::mem::swap(&mut buf, &mut next);
+        n = m;
+    }
+}
+
+// 尽量读满 `buf`，只有到达末尾时才会返回小于 `buf.len()` 的值
+fn read_full(reader: &mut dyn Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use std::io::Cursor;
+
+    use crate::utils::TestDir;
+
     #[test]
     fn test_blake3_sign_verify() -> anyhow::Result<()> {
         let blake3 = Blake3::load("fixtrues/blake3")?;
@@ -295,14 +1538,619 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_detached_signature() -> anyhow::Result<()> {
+        let sk = Ed25519Signer::load("fixtrues/ed25519.sk")?;
+        let pk = Ed25519Verifier::load("fixtrues/ed25519.pk")?;
+        assert_eq!(sk.key_id(), pk.key_id());
+        assert_eq!(sk.key_id().len(), 16);
+
+        let detached =
+            process_sign_detached("Cargo.toml", "fixtrues/ed25519.sk", TextSignFormat::Ed25519)?;
+        let parsed: DetachedSignature = serde_json::from_str(&detached)?;
+        assert_eq!(parsed.algorithm, "ed25519");
+        assert_eq!(parsed.key_id, pk.key_id());
+
+        let dir = TestDir::new()?;
+        let sig_file = &dir.file("detached.sig");
+        fs::write(sig_file, &detached)?;
+        assert!(process_verify_detached(
+            "Cargo.toml",
+            "fixtrues/ed25519.pk",
+            sig_file,
+            TextSignFormat::Ed25519
+        )?);
+        assert!(!process_verify_detached(
+            "fixtrues/policy.yaml",
+            "fixtrues/ed25519.pk",
+            sig_file,
+            TextSignFormat::Ed25519
+        )?);
+
+        // 共享密钥的 ID 不会泄露密钥本身，且换一把密钥会被识别出来
+        let detached =
+            process_sign_detached("Cargo.toml", "fixtrues/blake3", TextSignFormat::Blake3)?;
+        fs::write(sig_file, detached)?;
+        assert!(process_verify_detached(
+            "Cargo.toml",
+            "fixtrues/blake3",
+            sig_file,
+            TextSignFormat::Blake3
+        )?);
+        let other = dir.file("other.key");
+        fs::write(
+            &other,
+            &Blake3::generate(&mut CliRng::insecure_from_seed(1))?[0],
+        )?;
+        let err = process_verify_detached("Cargo.toml", &other, sig_file, TextSignFormat::Blake3)
+            .unwrap_err();
+        assert!(err.to_string().contains("不一致"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_detached_signature_rejects_algorithm_from_file() -> anyhow::Result<()> {
+        // 把公钥当作共享密钥签名，只持有公钥的攻击者也能得到“合法”的签名文件
+        let dir = TestDir::new()?;
+        let sig_file = &dir.file("forged.sig");
+        for format in [
+            TextSignFormat::Blake3,
+            TextSignFormat::HmacSha256,
+            TextSignFormat::HmacSha512,
+        ] {
+            let forged = process_sign_detached("Cargo.toml", "fixtrues/ed25519.pk", format)?;
+            fs::write(sig_file, forged)?;
+            let err = process_verify_detached(
+                "Cargo.toml",
+                "fixtrues/ed25519.pk",
+                sig_file,
+                TextSignFormat::Ed25519,
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("算法"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_minisign() -> anyhow::Result<()> {
+        let keys = process_gen_key(
+            TextKeyKind::Sign(TextSignFormat::Ed25519),
+            KeyFormat::Minisign,
+            None,
+            &mut CliRng::insecure_from_seed(11),
+        )?;
+        let dir = TestDir::new()?;
+        let (sk, pk, minisig) = (
+            &dir.file("minisign.key"),
+            &dir.file("minisign.pub"),
+            &dir.file("minisign.minisig"),
+        );
+        fs::write(sk, &keys[0])?;
+        fs::write(pk, &keys[1])?;
+
+        let signed = process_sign_minisign("Cargo.toml", sk, true, None)?;
+        assert!(signed.contains("\tfile:Cargo.toml\thashed\n"));
+        fs::write(minisig, &signed)?;
+        let comment = process_verify_minisign("Cargo.toml", pk, minisig)?.unwrap();
+        assert!(comment.starts_with("timestamp:"));
+        assert_eq!(
+            process_verify_minisign("fixtrues/blake3", pk, minisig)?,
+            None
+        );
+
+        // 其他格式的密钥使用由公钥派生的 ID，签名方与验证方一致
+        let signed = process_sign_minisign("Cargo.toml", "fixtrues/ed25519.sk", false, Some("x"))?;
+        fs::write(minisig, signed)?;
+        let comment = process_verify_minisign("Cargo.toml", "fixtrues/ed25519.pk", minisig)?;
+        assert_eq!(comment.as_deref(), Some("x"));
+        assert!(process_verify_minisign("Cargo.toml", pk, minisig).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blake3_generate_seeded() -> anyhow::Result<()> {
+        let key = Blake3::generate(&mut CliRng::insecure_from_seed(42))?;
+        assert_eq!(key, Blake3::generate(&mut CliRng::insecure_from_seed(42))?);
+        assert_eq!(
+            String::from_utf8(key[0].clone())?,
+            "7848b5d711bc9883996317a3f9c90269d56771005d540a19184939c9e8d0db2a"
+        );
+        let blake3 = Blake3::try_new(&key[0])?;
+        assert_eq!(HEXLOWER_PERMISSIVE.encode(&blake3.key).into_bytes(), key[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_blake3_key_validation() -> anyhow::Result<()> {
+        let hex = "00112233445566778899aabbccddeeff00112233445566778899AABBCCDDEEFF";
+        assert!(Blake3::try_new(hex.as_bytes()).is_ok());
+        assert!(Blake3::try_new(format!("{}\n", hex).as_bytes()).is_ok());
+        assert!(Blake3::try_new(&[7u8; 32]).is_ok());
+        assert!(Blake3::try_new(b"N71Vwb@fLk_DK7@e$YHHjn*uE5U=(K1o\n").is_ok());
+        assert!(Blake3::try_new(b"too short").is_err());
+        assert!(Blake3::try_new(&[7u8; 33]).is_err());
+        assert!(Blake3::try_new(format!("{}00", hex).as_bytes()).is_err());
+        assert!(Blake3::try_new(hex.replace('0', "x").as_bytes()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ed25519_generate_seeded() -> anyhow::Result<()> {
+        let key = Ed25519Signer::generate(&mut CliRng::insecure_from_seed(42))?;
+        assert_eq!(
+            key,
+            Ed25519Signer::generate(&mut CliRng::insecure_from_seed(42))?
+        );
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(&key[1]),
+            "eO2iG6BKFeIAD-iBD-PlZ0HSO7muRKqdW7Ibdmdf80s"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_blake3_streaming() -> anyhow::Result<()> {
+        let blake3 = Blake3::load("fixtrues/blake3")?;
+        let data = vec![42u8; 1024 * 1024 + 7];
+        let signed = blake3.sign(&mut Cursor::new(&data))?;
+        assert_eq!(signed, blake3::keyed_hash(&blake3.key, &data).as_bytes());
+        assert!(blake3.verify(&mut Cursor::new(&data), &signed)?);
+        assert!(!blake3.verify(&mut Cursor::new(&data[1..]), &signed)?);
+        assert!(!blake3.verify(&mut Cursor::new(&data), &signed[..31])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ed25519ph_rfc8032() -> anyhow::Result<()> {
+        // RFC 8032 7.3 Ed25519ph 测试向量
+        let sk = data_encoding::HEXLOWER
+            .decode(b"833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42")?;
+        let sig = data_encoding::HEXLOWER.decode(b"98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae4131f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406")?;
+        let signer = Ed25519phSigner {
+            key: Ed25519Signer::try_new(&sk)?.key,
+        };
+        assert_eq!(signer.sign(&mut Cursor::new(b"abc"))?, sig);
+
+        let verifier = Ed25519phVerifier {
+            key: signer.key.verifying_key(),
+        };
+        assert!(verifier.verify(&mut Cursor::new(b"abc"), &sig)?);
+        assert!(!verifier.verify(&mut Cursor::new(b"abd"), &sig)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ed25519ph_large_input() -> anyhow::Result<()> {
+        let sk = Ed25519phSigner::load("fixtrues/ed25519.sk")?;
+        let pk = Ed25519phVerifier::load("fixtrues/ed25519.pk")?;
+        let data = vec![7u8; 1024 * 1024 + 3];
+        let signed = sk.sign(&mut Cursor::new(&data))?;
+        assert!(pk.verify(&mut Cursor::new(&data), &signed)?);
+        // 预哈希签名不能当作纯 Ed25519 签名通过验证
+        let pure = Ed25519Verifier::load("fixtrues/ed25519.pk")?;
+        assert!(!pure.verify(&mut Cursor::new(&data), &signed)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hmac_sign_verify() -> anyhow::Result<()> {
+        // RFC 4231 测试用例 2，密钥文件内容为 "Jefe\n"
+        let data = b"what do ya want for nothing?";
+        let sha256 = HmacSha256::load("fixtrues/hmac.key")?;
+        let signed = sha256.sign(&mut Cursor::new(data))?;
+        assert_eq!(
+            signed
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(sha256.verify(&mut Cursor::new(data), &signed)?);
+        assert!(!sha256.verify(&mut Cursor::new(b"tampered"), &signed)?);
+        assert!(!sha256.verify(&mut Cursor::new(data), &signed[..16])?);
+
+        let sha512 = HmacSha512::load("fixtrues/hmac.key")?;
+        let signed = sha512.sign(&mut Cursor::new(data))?;
+        assert_eq!(
+            signed.iter().map(|b| format!("{:02x}", b)).collect::<String>(),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+        assert!(sha512.verify(&mut Cursor::new(data), &signed)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hmac_generate() -> anyhow::Result<()> {
+        let key = HmacSha256::generate(&mut CliRng::insecure_from_seed(42))?;
+        assert_eq!(key[0].len(), 64);
+        let signer = HmacSha256::try_new(&key[0])?;
+        let signed = signer.sign(&mut Cursor::new(b"hello"))?;
+        assert!(signer.verify(&mut Cursor::new(b"hello"), &signed)?);
+        assert!(HmacSha256::try_new(b"\n").is_err());
+        Ok(())
+    }
+
+    fn encrypt_vec(encryptor: &impl Encryptor, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut encrypted = Vec::new();
+        encryptor.encrypt(
+            &mut Cursor::new(data),
+            &mut encrypted,
+            &[],
+            &mut CliRng::os(),
+        )?;
+        Ok(encrypted)
+    }
+
+    fn decrypt_vec(decryptor: &impl Decryptor, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut decrypted = Vec::new();
+        decryptor.decrypt(&mut Cursor::new(data), &mut decrypted, &[])?;
+        Ok(decrypted)
+    }
+
     #[test]
     fn test_cha_cha20_poly1305_aead() -> anyhow::Result<()> {
-        let encrypted = ChaCha20Poly1305Aead::load("fixtrues/chacha20poly1305.key")?;
+        let key = ChaCha20Poly1305Aead::generate(&mut CliRng::insecure_from_seed(7))?;
+        assert_eq!(key[0].len(), 32);
+        let aead = ChaCha20Poly1305Aead::try_new(&key[0])?;
         let data = b"hello world";
-        let c = encrypted.encrypt(&mut Cursor::new(data))?;
-        let decrypted = encrypted.decrypt(&mut Cursor::new(c.as_bytes()))?;
-        assert_eq!(data, decrypted.as_bytes());
+        let c1 = encrypt_vec(&aead, data)?;
+        let c2 = encrypt_vec(&aead, data)?;
+        assert_ne!(c1, c2);
+        // nonce 取自调用方传入的随机源
+        let seeded = |seed| -> anyhow::Result<Vec<u8>> {
+            let mut c = Vec::new();
+            let mut rng = CliRng::insecure_from_seed(seed);
+            aead.encrypt(&mut &data[..], &mut c, &[], &mut rng)?;
+            Ok(c)
+        };
+        assert_eq!(seeded(1)?, seeded(1)?);
+        assert_ne!(seeded(1)?, seeded(2)?);
+        for c in [c1, c2] {
+            assert_eq!(decrypt_vec(&aead, &c)?, data);
+        }
+        assert!(ChaCha20Poly1305Aead::try_new(&key[0][..31]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cha_cha20_poly1305_legacy_key() -> anyhow::Result<()> {
+        let aead = ChaCha20Poly1305Aead::load("fixtrues/chacha20poly1305.key")?;
+        let data = b"hello world";
+
+        // 旧版本：固定 nonce，密文不带版本号
+        let legacy = aead
+            .cipher()
+            .encrypt(aead.legacy_nonce.as_ref().unwrap(), data.as_ref())
+            .unwrap();
+        assert_eq!(decrypt_vec(&aead, &legacy)?, data);
+
+        let c = encrypt_vec(&aead, data)?;
+        assert_eq!(decrypt_vec(&aead, &c)?, data);
+
+        // 两种格式都失败时保留按新格式解密的错误
+        let err = decrypt_vec(&aead, &c[..c.len() - 1]).unwrap_err();
+        let chain = format!("{:#}", err);
+        assert!(chain.contains("旧版"));
+        assert!(chain.contains("解密密文失败"));
+        Ok(())
+    }
+
+    const ALL_CIPHERS: [AeadCipher; 4] = [
+        AeadCipher::ChaCha20Poly1305,
+        AeadCipher::XChaCha20Poly1305,
+        AeadCipher::Aes256Gcm,
+        AeadCipher::Aes256GcmSiv,
+    ];
+
+    #[test]
+    fn test_stream_ciphers() -> anyhow::Result<()> {
+        let key = Aes256GcmAead::generate(&mut CliRng::insecure_from_seed(3))?;
+        let data = vec![5u8; STREAM_CHUNK_SIZE + 100];
+        let encrypted = [
+            encrypt_vec(&ChaCha20Poly1305Aead::try_new(&key[0])?, &data)?,
+            encrypt_vec(&XChaCha20Poly1305Aead::try_new(&key[0])?, &data)?,
+            encrypt_vec(&Aes256GcmAead::try_new(&key[0])?, &data)?,
+            encrypt_vec(&Aes256GcmSivAead::try_new(&key[0])?, &data)?,
+        ];
+        // 任意一种密钥对象都能按头部中的算法解密
+        let decryptor = Aes256GcmSivAead::try_new(&key[0])?;
+        for (c, cipher) in encrypted.iter().zip(ALL_CIPHERS) {
+            assert_eq!(c[..2], [CIPHERTEXT_VERSION, cipher_id(cipher)]);
+            assert_eq!(decrypt_vec(&decryptor, c)?, data);
+            assert_eq!(
+                decrypt_vec(&ChaCha20Poly1305Aead::try_new(&key[0])?, c)?,
+                data
+            );
+        }
+        // XChaCha20 的 nonce 前缀为 19 字节
+        assert_eq!(encrypted[1].len(), encrypted[0].len() + 12);
+
+        let mut unknown = encrypted[0].clone();
+        unknown[1] = 99;
+        assert!(decrypt_vec(&decryptor, &unknown).is_err());
+        assert!(Aes256GcmAead::try_new(&key[0][..16]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_matches_aead_stream_be32() -> anyhow::Result<()> {
+        use chacha20poly1305::aead::stream::EncryptorBE32;
+
+        // 用 `aead::stream::EncryptorBE32` 按同样的头部生成的密文，解密结果必须一致
+        let key = [1u8; 32];
+        let prefix = [2u8; 7];
+        let header = [CIPHERTEXT_VERSION, cipher_id(AeadCipher::ChaCha20Poly1305)];
+        let aad = [&header[..], &prefix].concat();
+        let mut encryptor =
+            EncryptorBE32::from_aead(ChaCha20Poly1305::new(&key.into()), &prefix.into());
+        let chunk = vec![3u8; STREAM_CHUNK_SIZE];
+        let mut c = aad.clone();
+        c.extend(
+            encryptor
+                .encrypt_next(Payload {
+                    msg: &chunk,
+                    aad: &aad,
+                })
+                .unwrap(),
+        );
+        c.extend(
+            encryptor
+                .encrypt_last(Payload {
+                    msg: b"tail",
+                    aad: &aad,
+                })
+                .unwrap(),
+        );
+        let plaintext = decrypt_vec(&ChaCha20Poly1305Aead::try_new(&key)?, &c)?;
+        assert_eq!(plaintext, [&chunk[..], b"tail"].concat());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_chunks() -> anyhow::Result<()> {
+        let aead = ChaCha20Poly1305Aead::try_new(&[1u8; 32])?;
+        let header = 2 + 7;
+        for len in [
+            0,
+            1,
+            STREAM_CHUNK_SIZE,
+            STREAM_CHUNK_SIZE + 1,
+            STREAM_CHUNK_SIZE * 2,
+        ] {
+            let data = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+            let c = encrypt_vec(&aead, &data)?;
+            let chunks = len.div_ceil(STREAM_CHUNK_SIZE).max(1);
+            assert_eq!(c.len(), header + len + chunks * TAG_SIZE);
+            assert_eq!(decrypt_vec(&aead, &c)?, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_detects_truncation_and_reordering() -> anyhow::Result<()> {
+        let aead = ChaCha20Poly1305Aead::try_new(&[1u8; 32])?;
+        let data = vec![42u8; STREAM_CHUNK_SIZE * 2 + 10];
+        let c = encrypt_vec(&aead, &data)?;
+        let header = 2 + 7;
+        let chunk = STREAM_CHUNK_SIZE + TAG_SIZE;
+
+        // 在块边界处截断
+        assert!(decrypt_vec(&aead, &c[..header + chunk * 2]).is_err());
+        assert!(decrypt_vec(&aead, &c[..c.len() - 1]).is_err());
+
+        // 交换前两块
+        let mut reordered = c[..header].to_vec();
+        reordered.extend_from_slice(&c[header + chunk..header + chunk * 2]);
+        reordered.extend_from_slice(&c[header..header + chunk]);
+        reordered.extend_from_slice(&c[header + chunk * 2..]);
+        assert!(decrypt_vec(&aead, &reordered).is_err());
+
+        assert!(decrypt_vec(&aead, &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_passphrase_aead() -> anyhow::Result<()> {
+        let params = argon2::Params::new(64, 1, 1, None).unwrap();
+        let data = b"hello world";
+        for cipher in ALL_CIPHERS {
+            let aead = PassphraseAead::with_params("correct horse", params.clone(), cipher);
+            let c = encrypt_vec(&aead, data)?;
+            assert_eq!(c[1], cipher_id(cipher));
+            // 解密时忽略自身的算法设置
+            let other = PassphraseAead::with_params(
+                "correct horse",
+                params.clone(),
+                AeadCipher::ChaCha20Poly1305,
+            );
+            assert_eq!(decrypt_vec(&other, &c)?, data);
+        }
+
+        let aead = PassphraseAead::with_params(
+            "correct horse",
+            params.clone(),
+            AeadCipher::ChaCha20Poly1305,
+        );
+        let c = encrypt_vec(&aead, data)?;
+        let wrong =
+            PassphraseAead::with_params("battery staple", params, AeadCipher::ChaCha20Poly1305);
+        assert!(decrypt_vec(&wrong, &c).is_err());
+
+        // 篡改头部中的参数会导致认证失败
+        let mut tampered = c.clone();
+        tampered[2] ^= 1;
+        assert!(decrypt_vec(&aead, &tampered).is_err());
+
+        let key = ChaCha20Poly1305Aead::try_new(&[0u8; 32])?;
+        let err = decrypt_vec(&key, &c).unwrap_err();
+        assert!(err.to_string().contains("--passphrase"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_key() -> anyhow::Result<()> {
+        let params = argon2::Params::new(64, 1, 1, None).unwrap();
+        let aead = PassphraseAead::with_params("pw", params, AeadCipher::ChaCha20Poly1305);
+        let key = Ed25519Signer::generate(&mut CliRng::insecure_from_seed(7))?;
+        let wrapped = wrap_key_with(&aead, &key[0], &mut CliRng::insecure_from_seed(8))?;
+        assert!(is_wrapped_key(&wrapped));
+        assert!(!is_wrapped_key(&key[0]));
+        assert!(wrapped.split(|&b| b == b'\n').all(|line| line.len() <= 64));
+
+        assert_eq!(unwrap_key(&wrapped, "pw")?, key[0]);
+        let err = unwrap_key(&wrapped, "wrong").unwrap_err();
+        assert!(err.to_string().contains("口令错误"));
+
+        let mut truncated = wrapped.clone();
+        truncated.truncate(wrapped.len() / 2);
+        assert!(unwrap_key(&truncated, "pw").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_encrypt_binary_and_armored() -> anyhow::Result<()> {
+        let key = "fixtrues/chacha20poly1305.key";
+        let dir = TestDir::new()?;
+        let input = &dir.file("input.bin");
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        fs::write(input, &data)?;
+
+        for binary in [true, false] {
+            let mut encrypted = Vec::new();
+            process_encrypt(
+                input,
+                &mut encrypted,
+                key,
+                AeadCipher::ChaCha20Poly1305,
+                b"tenant-42",
+                binary,
+                &mut CliRng::insecure_from_seed(3),
+            )?;
+            assert_eq!(encrypted.is_ascii(), !binary);
+
+            let ciphertext = &dir.file(&format!("{}.enc", binary));
+            fs::write(ciphertext, &encrypted)?;
+            let mut decrypted = Vec::new();
+            process_decrypt(ciphertext, &mut decrypted, key, b"tenant-42", binary)?;
+            assert_eq!(decrypted, data);
+            assert!(
+                process_decrypt(ciphertext, &mut Vec::new(), key, b"tenant-43", binary).is_err()
+            );
+        }
+        Ok(())
+    }
+
+    fn check_aad(aead: &(impl Encryptor + Decryptor), data: &[u8]) -> anyhow::Result<()> {
+        let mut c = Vec::new();
+        aead.encrypt(
+            &mut Cursor::new(data),
+            &mut c,
+            b"invoice.pdf",
+            &mut CliRng::insecure_from_seed(4),
+        )?;
+        let mut plaintext = Vec::new();
+        aead.decrypt(&mut Cursor::new(&c), &mut plaintext, b"invoice.pdf")?;
+        assert_eq!(plaintext, data);
+        for wrong in [&b"other.pdf"[..], b""] {
+            assert!(aead
+                .decrypt(&mut Cursor::new(&c), &mut Vec::new(), wrong)
+                .is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_aad_binds_ciphertext() -> anyhow::Result<()> {
+        let data = vec![8u8; STREAM_CHUNK_SIZE + 1];
+        let params = argon2::Params::new(64, 1, 1, None).unwrap();
+        check_aad(
+            &PassphraseAead::with_params("pw", params, AeadCipher::Aes256Gcm),
+            &data,
+        )?;
+        let keyed = Aes256GcmSivAead::try_new(&[4u8; 32])?;
+        check_aad(&keyed, &data)?;
+
+        // 附加数据不会出现在密文中
+        let c = encrypt_vec(&keyed, b"x")?;
+        let mut with_aad = Vec::new();
+        keyed.encrypt(
+            &mut Cursor::new(b"x"),
+            &mut with_aad,
+            b"context",
+            &mut CliRng::insecure_from_seed(0),
+        )?;
+        assert_eq!(c.len(), with_aad.len());
+
+        let age = AgeRecipients::parse(&["fixtrues/x25519.pub".to_string()])?;
+        assert!(age
+            .encrypt(
+                &mut Cursor::new(b"x"),
+                &mut Vec::new(),
+                b"context",
+                &mut CliRng::insecure_from_seed(0)
+            )
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_age_x25519() -> anyhow::Result<()> {
+        let key = AgeIdentities::generate(&mut CliRng::insecure_from_seed(11))?;
+        let other = String::from_utf8(key[1].clone())?;
+        assert!(other.starts_with("age1"));
+        let sk = String::from_utf8(key[0].clone())?;
+        assert!(sk.contains(&format!("# public key: {}", other.trim())));
+
+        let recipients = ["fixtrues/x25519.pub".to_string(), other.trim().to_string()];
+        let encryptor = AgeRecipients::parse(&recipients)?;
+        let data = vec![7u8; 100_000];
+        let c = encrypt_vec(&encryptor, &data)?;
+        assert!(c.starts_with(b"age-encryption.org/v1\n"));
+
+        let fixture = AgeIdentities::load_all(&["fixtrues/x25519.key".to_string()])?;
+        assert_eq!(decrypt_vec(&fixture, &c)?, data);
+        let other = AgeIdentities {
+            identities: vec![parse_age_key(age_key_lines(&sk).next().unwrap())?],
+        };
+        assert_eq!(decrypt_vec(&other, &c)?, data);
+
+        let stranger = AgeIdentities::generate(&mut CliRng::insecure_from_seed(12))?;
+        let stranger = AgeIdentities {
+            identities: vec![parse_age_key(
+                age_key_lines(std::str::from_utf8(&stranger[0])?)
+                    .next()
+                    .unwrap(),
+            )?],
+        };
+        assert!(decrypt_vec(&stranger, &c).is_err());
+        assert!(AgeRecipients::parse(&["age1invalid".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_age_armored() -> anyhow::Result<()> {
+        let mut armored = Vec::new();
+        process_encrypt_to_recipients(
+            "Cargo.toml",
+            &mut armored,
+            &["fixtrues/x25519.pub".to_string()],
+            false,
+            &mut CliRng::insecure_from_seed(5),
+        )?;
+        assert!(armored.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----"));
 
+        let dir = TestDir::new()?;
+        let path = dir.file("armored.age");
+        fs::write(&path, &armored)?;
+        let mut decrypted = Vec::new();
+        process_decrypt_with_identities(
+            &path,
+            &mut decrypted,
+            &["fixtrues/x25519.key".to_string()],
+        )?;
+        assert_eq!(decrypted, fs::read("Cargo.toml")?);
         Ok(())
     }
 }
@@ -1,4 +1,11 @@
-use std::io::Read;
+use std::{
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+#[cfg(test)]
+use {rand::SeedableRng, rand_chacha::ChaCha20Rng};
 
 pub fn get_reader(input: &str) -> anyhow::Result<Box<dyn Read>> {
     if input == "-" {
@@ -7,3 +14,248 @@ pub fn get_reader(input: &str) -> anyhow::Result<Box<dyn Read>> {
         Ok(Box::new(std::fs::File::open(input)?))
     }
 }
+
+pub fn get_writer(output: &str) -> anyhow::Result<Box<dyn Write>> {
+    if output == "-" {
+        Ok(Box::new(std::io::stdout()))
+    } else {
+        Ok(Box::new(BufWriter::new(std::fs::File::create(output)?)))
+    }
+}
+
+/// 把 `reader` 的全部内容分块交给 `update`，用于流式计算哈希或 MAC
+pub fn update_from_reader(
+    reader: &mut dyn Read,
+    update: impl FnMut(&[u8]),
+) -> std::io::Result<u64> {
+    struct UpdateWriter<F>(F);
+
+    impl<F: FnMut(&[u8])> Write for UpdateWriter<F> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            (self.0)(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    std::io::copy(reader, &mut UpdateWriter(update))
+}
+
+/// 写入文件时先写到同一目录下的临时文件，调用 [`AtomicWriter::commit`] 后才替换目标文件。
+///
+/// 中途出错（密钥错误、解密失败等）时临时文件会被删除，目标文件保持原样，
+/// 因此 `-i f -o f` 也不会破坏输入。输出为 `-` 时直接写到标准输出。
+pub enum AtomicWriter {
+    Stdout(std::io::Stdout),
+    File {
+        file: BufWriter<tempfile::NamedTempFile>,
+        path: PathBuf,
+        overwrite: bool,
+    },
+}
+
+impl AtomicWriter {
+    pub fn new(output: &str) -> anyhow::Result<Self> {
+        Self::open(output, true)
+    }
+
+    /// 与 [`AtomicWriter::new`] 相同，但目标文件已存在时报错而不是覆盖。
+    pub fn create_new(output: &str) -> anyhow::Result<Self> {
+        if output != "-" && Path::new(output).exists() {
+            anyhow::bail!("文件 {} 已存在", output);
+        }
+        Self::open(output, false)
+    }
+
+    fn open(output: &str, overwrite: bool) -> anyhow::Result<Self> {
+        if output == "-" {
+            return Ok(Self::Stdout(std::io::stdout()));
+        }
+        let path = PathBuf::from(output);
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut builder = tempfile::Builder::new();
+        // 临时文件默认为 0600，改为与普通新建文件一样的 0666 再由 umask 收紧
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            builder.permissions(std::fs::Permissions::from_mode(0o666));
+        }
+        let file = builder.tempfile_in(dir)?;
+        Ok(Self::File {
+            file: BufWriter::new(file),
+            path,
+            overwrite,
+        })
+    }
+
+    /// 写入完成，用临时文件替换目标文件。
+    pub fn commit(self) -> anyhow::Result<()> {
+        match self {
+            Self::Stdout(mut stdout) => stdout.flush()?,
+            Self::File {
+                file,
+                path,
+                overwrite,
+            } => {
+                let file = file.into_inner().map_err(|e| e.into_error())?;
+                // 覆盖已有文件时保留其权限
+                if let Ok(metadata) = std::fs::metadata(&path) {
+                    file.as_file().set_permissions(metadata.permissions())?;
+                }
+                let persisted = if overwrite {
+                    file.persist(&path)
+                } else {
+                    file.persist_noclobber(&path)
+                };
+                persisted.map_err(|e| anyhow::anyhow!("无法写入 {}: {}", path.display(), e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for AtomicWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::File { file, .. } => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::File { file, .. } => file.flush(),
+        }
+    }
+}
+
+/// 在终端上读取口令（不回显），`confirm` 为真时要求再输入一次确认。
+pub fn prompt_passphrase(confirm: bool) -> anyhow::Result<String> {
+    let passphrase = rpassword::prompt_password("请输入口令: ")?;
+    if passphrase.is_empty() {
+        anyhow::bail!("口令不能为空");
+    }
+    if confirm && rpassword::prompt_password("请再次输入口令: ")? != passphrase {
+        anyhow::bail!("两次输入的口令不一致");
+    }
+    Ok(passphrase)
+}
+
+/// 随机数来源，默认使用操作系统的 CSPRNG；固定种子的模式只在测试构建中存在。
+#[derive(Debug, Default)]
+pub enum CliRng {
+    #[default]
+    Os,
+    /// 固定种子的 ChaCha20，输出完全可预测，只能用于测试！
+    #[cfg(test)]
+    InsecureSeeded(Box<ChaCha20Rng>),
+}
+
+impl CliRng {
+    pub fn os() -> Self {
+        Self::Os
+    }
+
+    /// 不安全：相同的种子总会得到相同的输出，仅用于编写确定性测试。
+    #[cfg(test)]
+    pub fn insecure_from_seed(seed: u64) -> Self {
+        Self::InsecureSeeded(Box::new(ChaCha20Rng::seed_from_u64(seed)))
+    }
+}
+
+impl RngCore for CliRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            CliRng::Os => OsRng.next_u32(),
+            #[cfg(test)]
+            CliRng::InsecureSeeded(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            CliRng::Os => OsRng.next_u64(),
+            #[cfg(test)]
+            CliRng::InsecureSeeded(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            CliRng::Os => OsRng.fill_bytes(dest),
+            #[cfg(test)]
+            CliRng::InsecureSeeded(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            CliRng::Os => OsRng.try_fill_bytes(dest),
+            #[cfg(test)]
+            CliRng::InsecureSeeded(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+// 正式构建中只有 OsRng；测试中的种子模式同样是 ChaCha20 流，只是种子公开。
+impl CryptoRng for CliRng {}
+
+/// 测试用的临时目录：每个测试独占一个，丢弃时（包括断言失败时）连同其中的文件一起删除。
+#[cfg(test)]
+pub struct TestDir(tempfile::TempDir);
+
+#[cfg(test)]
+impl TestDir {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self(tempfile::tempdir()?))
+    }
+
+    pub fn path(&self) -> &Path {
+        self.0.path()
+    }
+
+    /// 目录中名为 `name` 的文件路径，可以直接传给接受 `&str` 路径的函数
+    pub fn file(&self, name: &str) -> String {
+        self.0.path().join(name).to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_writer_permissions() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = |path: &str| -> anyhow::Result<u32> {
+            Ok(std::fs::metadata(path)?.permissions().mode() & 0o777)
+        };
+        let dir = TestDir::new()?;
+        // 新文件与 `File::create` 创建的文件权限相同
+        let plain = dir.file("plain");
+        std::fs::File::create(&plain)?;
+        let output = dir.file("output");
+        let mut writer = AtomicWriter::new(&output)?;
+        writer.write_all(b"new")?;
+        writer.commit()?;
+        assert_eq!(mode(&output)?, mode(&plain)?);
+
+        // 覆盖时保留原有权限
+        std::fs::set_permissions(&output, std::fs::Permissions::from_mode(0o640))?;
+        let mut writer = AtomicWriter::new(&output)?;
+        writer.write_all(b"overwritten")?;
+        writer.commit()?;
+        assert_eq!(mode(&output)?, 0o640);
+        assert_eq!(std::fs::read(&output)?, b"overwritten");
+        Ok(())
+    }
+}